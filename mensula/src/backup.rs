@@ -0,0 +1,74 @@
+use std::ffi::CStr;
+use std::os::raw::c_int;
+
+use sqlite::Connection;
+use sqlite3_sys as ffi;
+
+use crate::{Error, Result};
+
+/// The number of pages copied before other connections get a chance to write
+const PAGES_PER_STEP: c_int = 256;
+/// How long to wait if the source database is locked by another connection
+const LOCKED_SLEEP_MS: c_int = 50;
+/// How often to wait for a lock before giving up, so a connection holding it for long can not block forever
+const LOCKED_RETRIES: usize = 200;
+
+/// Copies the `main` database of `source` into `destination` with the online backup API,
+/// so the copy is consistent even while other connections write to the source.
+///
+/// Fails with `SQLITE_BUSY` if a database stays locked for [`LOCKED_RETRIES`] tries in a row.
+///
+/// See https://www.sqlite.org/backup.html
+pub(crate) fn copy(source: &Connection, destination: &Connection) -> Result<()> {
+    let main = c"main".as_ptr();
+
+    // Safe because both connections outlive the backup, which is always finished
+    unsafe {
+        let backup = ffi::sqlite3_backup_init(destination.as_raw(), main, source.as_raw(), main);
+
+        if backup.is_null() {
+            return Err(error(destination));
+        }
+
+        let mut retries = 0;
+
+        loop {
+            match ffi::sqlite3_backup_step(backup, PAGES_PER_STEP) {
+                ffi::SQLITE_OK => retries = 0,
+                ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED if retries < LOCKED_RETRIES => {
+                    retries += 1;
+                    ffi::sqlite3_sleep(LOCKED_SLEEP_MS);
+                }
+                ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => {
+                    ffi::sqlite3_backup_finish(backup);
+
+                    return Err(sqlite::Error {
+                        code: Some(ffi::SQLITE_BUSY as isize),
+                        message: Some("backup timed out waiting for a locked database".to_owned()),
+                    }
+                    .into());
+                }
+                _ => break,
+            }
+        }
+
+        match ffi::sqlite3_backup_finish(backup) {
+            ffi::SQLITE_OK => Ok(()),
+            _ => Err(error(destination)),
+        }
+    }
+}
+
+/// The last error of `connection`
+unsafe fn error(connection: &Connection) -> Error {
+    let raw = connection.as_raw();
+    let message = CStr::from_ptr(ffi::sqlite3_errmsg(raw))
+        .to_string_lossy()
+        .into_owned();
+
+    sqlite::Error {
+        code: Some(ffi::sqlite3_errcode(raw) as isize),
+        message: Some(message),
+    }
+    .into()
+}
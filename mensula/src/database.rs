@@ -1,122 +1,471 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
 
-use sqlite::{Connection, Statement};
+use sqlite::{Connection, OpenFlags, State, Statement, Value};
+use sqlite3_sys as ffi;
 
-use crate::meta::{Difference, Meta};
-use crate::query::{CreateTableQuery, DeleteQuery, InsertQuery, SelectQuery};
-use crate::table::{Insertable, Readable};
-use crate::{Key, Table};
+use crate::backup;
+use crate::meta::{ColumnDifference, Difference, Meta};
+use crate::error::{Error, Result};
+use crate::logger::{MigrationInfo, QueryInfo, QueryLogger};
+use crate::observer::{Change, ChangeKind, Notification, Observers};
+use crate::query::{
+    CreateTableQuery, DeleteQuery, FulltextQuery, IndexQuery, InsertQuery, MigrateTableQuery,
+    SelectQuery, UpdateQuery,
+};
+use crate::table::{FromRow, Insertable, Readable};
+use crate::statement_cache::{CachedStatement, StatementCache, StatementCacheStats};
+use crate::transaction::Transaction;
+use crate::{Column, Filter, FilterValue, Table};
 
 pub struct Database {
+    // Needs to be dropped before the connection, see `StatementCache`
+    statements: StatementCache,
     connection: Connection,
     meta: Meta,
-    meta_path: PathBuf,
+    logger: Option<Arc<dyn QueryLogger>>,
+    observers: Arc<Observers>,
+    /// Changes of the running transaction that are reported to the observers after the commit
+    changes: RefCell<Vec<Change>>,
+    pub(crate) transaction_depth: Cell<usize>,
 }
 
 impl Database {
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, sqlite::Error> {
-        let path = path.as_ref().to_owned();
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
 
-        let connection = sqlite::open(&path)?;
+        let mut database = Self::from_connection(sqlite::open(path)?)?;
 
-        let mut meta_path = path;
-        meta_path.set_extension("meta.toml");
+        if database.meta.is_empty() {
+            database.import_meta_file(path)?;
+        }
 
-        let meta = match fs::read_to_string(&meta_path) {
-            Ok(s) => toml::from_str(&s).expect(&format!(
-                "could not read DB meta file at '{}'",
-                meta_path.display()
-            )),
-            Err(_) => Meta::default(),
-        };
+        Ok(database)
+    }
+
+    /// Opens a new empty database that only exists as long as it is not dropped.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(sqlite::open(":memory:")?)
+    }
 
+    fn from_connection(connection: Connection) -> Result<Self> {
         connection.execute("PRAGMA foreign_keys = ON")?;
 
-        Ok(Self {
+        let mut database = Self {
+            statements: StatementCache::new(),
             connection,
-            meta,
-            meta_path,
-        })
+            meta: Meta::default(),
+            logger: None,
+            observers: Arc::default(),
+            changes: RefCell::new(Vec::new()),
+            transaction_depth: Cell::new(0),
+        };
+
+        database.meta = Meta::load(&database)?;
+
+        Ok(database)
+    }
+
+    /// Imports the `meta.toml` file that was stored next to the database by older versions.
+    fn import_meta_file(&mut self, path: &Path) -> Result<()> {
+        let meta_path = path.with_extension("meta.toml");
+
+        let content = match fs::read_to_string(&meta_path) {
+            Ok(content) => content,
+            Err(_) => return Ok(()),
+        };
+
+        let meta: Meta = toml::from_str(&content).map_err(|err| {
+            Error::Migration(format!(
+                "could not import DB meta file at '{}': {}",
+                meta_path.display(),
+                err
+            ))
+        })?;
+
+        self.transaction(|database| meta.store(database))?;
+        self.meta = meta;
+
+        Ok(())
+    }
+
+    /// Writes a consistent copy of the database to `path`, replacing any database stored there.
+    ///
+    /// Other connections can keep reading and writing while the backup runs.
+    /// The copy contains the meta of the tables, so it can be opened and registered like the original.
+    pub fn backup_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let destination = sqlite::open(path)?;
+
+        backup::copy(&self.connection, &destination)
     }
 
-    pub fn register<T: Table>(&mut self) -> sqlite::Result<()> {
-        let difference = match self.meta.get_difference::<T>() {
+    /// Replaces the content of the database with the backup at `path`.
+    ///
+    /// Tables need to be registered again afterwards,
+    /// because the backup may have been made with an older schema.
+    pub fn restore_from<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let source = Connection::open_with_flags(path, OpenFlags::new().set_read_only())?;
+
+        self.statements.clear();
+
+        backup::copy(&source, &self.connection)?;
+
+        self.meta = Meta::load(self)?;
+
+        Ok(())
+    }
+
+    /// Creates the table of `T` or migrates it to the current definition of `T`,
+    /// including its indices and full-text columns.
+    pub fn register<T: Table>(&mut self) -> Result<()> {
+        self.statements.clear();
+
+        let difference = match self.meta.get_difference::<T>()? {
             Some(difference) => difference,
             None => return Ok(()),
         };
 
         match difference {
             Difference::NewTable => self.create_table::<T>(),
-            Difference::Columns(difference) => {
-                println!(
-                    "found difference in table '{}':\n{:?}",
-                    T::table_name(),
-                    difference
-                );
-                todo!()
+            Difference::Primary {
+                before,
+                after,
+                columns,
+            } => self.migrate_table::<T>(columns, Some((&before, &after))),
+            Difference::Columns(difference) => self.migrate_table::<T>(difference, None),
+            Difference::Indices => self.update_indices::<T>(),
+        }
+    }
+
+    /// Runs `f` inside a transaction, which is committed if `f` returns `Ok`
+    /// and rolled back if it returns `Err` or panics.
+    ///
+    /// Calling this inside another transaction creates a savepoint instead,
+    /// so only the changes of the inner closure are rolled back.
+    pub fn transaction<R, E, F>(&self, f: F) -> std::result::Result<R, E>
+    where
+        F: FnOnce(&Database) -> std::result::Result<R, E>,
+        E: From<Error>,
+    {
+        let transaction = Transaction::begin(self)?;
+
+        match f(&transaction) {
+            Ok(value) => {
+                transaction.commit()?;
+                Ok(value)
+            }
+            Err(err) => {
+                let _ = transaction.rollback();
+                Err(err)
             }
         }
     }
 
-    fn save_meta(&self) {
-        self.meta.save(&self.meta_path);
+    /// Passes every statement run from now on to `logger`.
+    pub fn set_logger<L: QueryLogger + 'static>(&mut self, logger: L) {
+        self.logger = Some(Arc::new(logger));
+    }
+
+    pub(crate) fn set_shared_logger(&mut self, logger: Option<Arc<dyn QueryLogger>>) {
+        self.logger = logger;
+    }
+
+    /// Calls `f` with the keys and rows after rows of `T` were inserted and committed.
+    ///
+    /// Inserting a row with an existing key replaces it, which is reported as insert as well.
+    /// The row of each key is at the same position, keys of rows deleted before the commit are left out.
+    pub fn on_insert<T, F>(&mut self, f: F)
+    where
+        T: Table + Readable<T> + 'static,
+        F: Fn(&[T::Primary], &[T]) + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.observers).on_insert(f);
+    }
+
+    /// Calls `f` with the keys and new rows after rows of `T` were updated and committed.
+    ///
+    /// The row of each key is at the same position, keys of rows deleted before the commit are left out.
+    pub fn on_update<T, F>(&mut self, f: F)
+    where
+        T: Table + Readable<T> + 'static,
+        F: Fn(&[T::Primary], &[T]) + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.observers).on_update(f);
+    }
+
+    /// Calls `f` with the keys after rows of `T` were deleted and committed.
+    ///
+    /// Rows deleted by a cascading foreign key are not reported.
+    pub fn on_delete<T, F>(&mut self, f: F)
+    where
+        T: Table + 'static,
+        F: Fn(&[T::Primary]) + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.observers).on_delete::<T, F>(f);
+    }
+
+    pub(crate) fn set_observers(&mut self, observers: Arc<Observers>) {
+        self.observers = observers;
+    }
+
+    pub(crate) fn is_observed<T: Table>(&self, kind: ChangeKind) -> bool {
+        self.observers.is_observed::<T>(kind)
+    }
+
+    /// Whether an observed change has to run in its own transaction.
+    ///
+    /// The observers read the changed rows before the commit,
+    /// so a failing read rolls the change back instead of failing after it was already committed.
+    pub(crate) fn needs_implicit_transaction<T: Table>(&self, kind: ChangeKind) -> bool {
+        self.transaction_depth.get() == 0 && self.is_observed::<T>(kind)
+    }
+
+    /// Remembers the keys of changed rows until the transaction is committed.
+    ///
+    /// Observed changes always run inside a transaction, see [`Self::needs_implicit_transaction`].
+    pub(crate) fn record_change<T: Table>(&self, kind: ChangeKind, keys: Vec<T::Primary>) {
+        if keys.is_empty() || !self.is_observed::<T>(kind) {
+            return;
+        }
+
+        self.changes.borrow_mut().push(Change::new::<T>(kind, keys));
+    }
+
+    /// Takes the recorded changes and reads what the observers need, before the changes are committed.
+    pub(crate) fn prepare_notifications(&self) -> Result<Vec<Notification<'_>>> {
+        if self.observers.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let changes = std::mem::take(&mut *self.changes.borrow_mut());
+
+        self.observers.prepare(self, changes)
+    }
+
+    /// The number of recorded changes, to discard the changes of a rolled back savepoint.
+    pub(crate) fn change_mark(&self) -> usize {
+        self.changes.borrow().len()
+    }
+
+    pub(crate) fn discard_changes(&self, mark: usize) {
+        self.changes.borrow_mut().truncate(mark);
+    }
+
+    pub(crate) fn log_query(&self, query: &QueryInfo) {
+        if let Some(logger) = &self.logger {
+            logger.log(query);
+        }
     }
 
-    pub(crate) fn execute<S: AsRef<str>>(&self, query: S) -> sqlite::Result<()> {
+    pub(crate) fn execute<S: AsRef<str>>(&self, query: S) -> Result<()> {
         let query = query.as_ref();
+
+        let changes_before = self.connection.total_change_count();
+        let start = Instant::now();
         let result = self.connection.execute(query);
-        match result {
-            Ok(value) => Ok(value),
-            Err(err) => {
-                println!("failed to execute '{}': {}", query, err);
-                Err(err)
+        let duration = start.elapsed();
+
+        self.log_query(&QueryInfo {
+            sql: query,
+            parameters: 0,
+            duration,
+            rows: self.connection.total_change_count() - changes_before,
+        });
+
+        Ok(result?)
+    }
+
+    /// Reuses the statement if the same query was prepared before.
+    pub(crate) fn prepare<S: AsRef<str>>(&self, query: S) -> Result<CachedStatement<'_>> {
+        let query = query.as_ref();
+
+        let statement = match self.statements.take(query) {
+            Some(statement) => statement,
+            None => {
+                let statement = self.connection.prepare(query)?;
+                // See `StatementCache` for why this is sound
+                unsafe { std::mem::transmute::<Statement<'_>, Statement<'static>>(statement) }
             }
+        };
+
+        Ok(CachedStatement::new(query.to_owned(), statement, self))
+    }
+
+    pub(crate) fn statements(&self) -> &StatementCache {
+        &self.statements
+    }
+
+    /// Runs any SQL query, binding `params` to its `?` placeholders in order, and reads every row into `R`.
+    ///
+    /// `R` reads the columns by position, like tuples or structs deriving [`crate::FromRow`].
+    /// Like every other query it is part of the transaction if it runs inside of [`Self::transaction`].
+    pub fn query_as<R, S, P>(&self, sql: S, params: P) -> Result<Vec<R>>
+    where
+        R: FromRow,
+        S: AsRef<str>,
+        P: IntoIterator<Item = FilterValue>,
+    {
+        let mut statement = self.prepare(sql)?;
+
+        for (index, value) in params.into_iter().enumerate() {
+            statement.bind::<(_, Value)>((index + 1, value.into()))?;
+        }
+
+        let mut rows = Vec::new();
+
+        while let State::Row = statement.next()? {
+            rows.push(R::from_row(&statement.row())?);
         }
+
+        Ok(rows)
+    }
+
+    pub fn statement_cache_stats(&self) -> StatementCacheStats {
+        self.statements.stats()
     }
 
-    pub(crate) fn prepare<S: AsRef<str>>(&self, query: S) -> sqlite::Result<Statement> {
-        self.connection.prepare(query)
+    /// The number of rows changed by the last finished statement
+    pub(crate) fn change_count(&self) -> usize {
+        self.connection.change_count()
+    }
+
+    /// Whether no transaction is open on the connection, including ones begun with raw SQL
+    pub(crate) fn is_autocommit(&self) -> bool {
+        // Safe because the connection is open as long as the database exists
+        unsafe { ffi::sqlite3_get_autocommit(self.connection.as_raw()) != 0 }
     }
 }
 
 impl Database {
-    fn create_table<T: Table>(&mut self) -> sqlite::Result<()> {
-        CreateTableQuery::<T>::new().run(&self)?;
+    fn create_table<T: Table>(&mut self) -> Result<()> {
+        self.transaction(|database| {
+            CreateTableQuery::<T>::new().run(database)?;
+            IndexQuery::<T>::new(Vec::new()).run(database)?;
+            FulltextQuery::<T>::new().run(database)?;
+            Meta::store_table::<T>(database)
+        })?;
 
-        self.meta.update_table::<T>();
-        self.save_meta();
+        self.meta.update_table::<T>()?;
 
         Ok(())
     }
 
-    pub fn insert<I: Insertable<impl Table>>(&self, data: I) -> Option<Key> {
-        match InsertQuery::new(data).run(&self) {
-            Ok(key) => Some(key),
-            Err(err) => {
-                println!("{:?}", err);
-                None
+    /// Migrates the table and reports the migration to the logger.
+    ///
+    /// `primary` contains the primary columns before and after, if they changed.
+    fn migrate_table<T: Table>(
+        &mut self,
+        difference: HashMap<String, ColumnDifference>,
+        primary: Option<(&str, &str)>,
+    ) -> Result<()> {
+        let mut added = Vec::new();
+        let mut dropped = Vec::new();
+        let mut changed = Vec::new();
+
+        for (name, column) in &difference {
+            match (&column.before, &column.after) {
+                (None, _) => added.push(name.clone()),
+                (_, None) => dropped.push(name.clone()),
+                _ => changed.push(name.clone()),
             }
         }
+
+        let dropped_indices = self.meta.get_dropped_indices::<T>()?;
+
+        let mut query = MigrateTableQuery::<T>::new(difference, dropped_indices);
+
+        if primary.is_some() {
+            query = query.primary_changed();
+        }
+
+        query.run(self)?;
+
+        self.meta.update_table::<T>()?;
+
+        if let Some(logger) = &self.logger {
+            fn names(columns: &mut [String]) -> Vec<&str> {
+                columns.sort();
+                columns.iter().map(String::as_str).collect()
+            }
+
+            logger.log_migration(&MigrationInfo {
+                table: T::table_name(),
+                primary,
+                added: names(&mut added),
+                dropped: names(&mut dropped),
+                changed: names(&mut changed),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn update_indices<T: Table>(&mut self) -> Result<()> {
+        let dropped_indices = self.meta.get_dropped_indices::<T>()?;
+
+        self.transaction(|database| {
+            IndexQuery::<T>::new(dropped_indices).run(database)?;
+            FulltextQuery::<T>::new().run(database)?;
+            Meta::store_table::<T>(database)
+        })?;
+
+        self.meta.update_table::<T>()?;
+
+        Ok(())
+    }
+
+    pub fn insert<T: Table, I: Insertable<T>>(&self, data: I) -> Result<T::Primary> {
+        InsertQuery::new(data).run(self)
     }
 
-    pub fn get_all<T: Table + Readable<T>>(&self) -> Option<Vec<T>> {
+    pub fn get_all<T: Table + Readable<T>>(&self) -> Result<Vec<T>> {
         SelectQuery::<T>::new().get_all(self)
     }
 
-    pub fn get<T: Table + Readable<T>>(&self, key: Key) -> Option<T> {
+    /// Reads the row with the primary key `key`, a tuple for a composite primary key.
+    pub fn get<T: Table + Readable<T>>(&self, key: T::Primary) -> Result<T> {
         if !self.meta.has_table::<T>() {
-            println!("Table '{}' not registered", T::table_name());
-            return None;
+            return Err(Error::NotRegistered {
+                table: T::table_name().to_owned(),
+            });
         }
-        SelectQuery::new()
-            .filter(T::primary_column().eq(key))
-            .get_first(self)
+        SelectQuery::<T>::new()
+            .filter(Filter::primary(key))
+            .get_first(self)?
+            .ok_or(Error::NotFound)
     }
 
-    pub fn delete<T: Table>(&self, id: Key) -> sqlite::Result<()> {
-        DeleteQuery::<T>::new(id).run(self)
+    pub fn delete<T: Table>(&self, id: T::Primary) -> Result<()> {
+        DeleteQuery::<T>::new(id).run(self)?;
+
+        Ok(())
+    }
+
+    /// Undoes the deletion of a row of a table with `#[soft_delete]`.
+    ///
+    /// Fails with [`Error::NotSoftDelete`] for other tables, as their rows are deleted for good.
+    pub fn restore<T: Table>(&self, id: T::Primary) -> Result<()> {
+        if !T::is_soft_delete() {
+            return Err(Error::NotSoftDelete {
+                table: T::table_name().to_owned(),
+            });
+        }
+
+        let restored = UpdateQuery::<T>::new()
+            .set(Column::deleted_at(), FilterValue::Null)
+            .filter(Filter::primary(id))
+            .run(self)?;
+
+        if restored == 0 {
+            return Err(Error::NotFound);
+        }
+
+        Ok(())
     }
 }
 
@@ -125,7 +474,9 @@ impl Debug for Database {
         f.debug_struct("Database")
             .field("connection", &"[...]".to_string())
             .field("meta", &self.meta)
-            .field("meta_path", &self.meta_path)
+            .field("transaction_depth", &self.transaction_depth.get())
+            .field("statement_cache", &self.statements.stats())
+            .field("logger", &self.logger.is_some())
             .finish()
     }
 }
@@ -0,0 +1,75 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Any error reported by SQLite that has no more specific variant
+    Sqlite(sqlite::Error),
+    /// A `UNIQUE`, `NOT NULL`, `CHECK` or `PRIMARY KEY` constraint failed
+    ConstraintViolation { table: String, column: String },
+    /// The table was never passed to [`crate::Database::register`]
+    NotRegistered { table: String },
+    /// The value of a column could not be read into the requested type
+    Decode { column: String },
+    /// No row matched the query
+    NotFound,
+    /// The schema of a table could not be migrated safely
+    Migration(String),
+    /// Deleted rows were restored in a table without `#[soft_delete]`, which deletes them for good
+    NotSoftDelete { table: String },
+    /// A `NaN` or infinite real was used as default value, which SQLite has no literal for
+    NotFinite(f64),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Parses messages in the form of `UNIQUE constraint failed: User.name`
+    fn parse_constraint_violation(message: &str) -> Option<Self> {
+        let (_, columns) = message.split_once("constraint failed: ")?;
+
+        let mut table = None;
+        let mut column_names = Vec::new();
+
+        for column in columns.split(", ") {
+            let (table_name, column_name) = column.split_once('.')?;
+            table.get_or_insert(table_name);
+            column_names.push(column_name);
+        }
+
+        Some(Self::ConstraintViolation {
+            table: table?.to_owned(),
+            column: column_names.join(", "),
+        })
+    }
+}
+
+impl From<sqlite::Error> for Error {
+    fn from(value: sqlite::Error) -> Self {
+        value
+            .message
+            .as_deref()
+            .and_then(Self::parse_constraint_violation)
+            .unwrap_or(Self::Sqlite(value))
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Sqlite(err) => write!(f, "sqlite error: {}", err),
+            Error::ConstraintViolation { table, column } => {
+                write!(f, "constraint violation on '{}.{}'", table, column)
+            }
+            Error::NotRegistered { table } => write!(f, "table '{}' not registered", table),
+            Error::Decode { column } => write!(f, "could not decode column '{}'", column),
+            Error::NotFound => write!(f, "not found"),
+            Error::Migration(message) => write!(f, "migration failed: {}", message),
+            Error::NotSoftDelete { table } => {
+                write!(f, "table '{}' has no soft delete, so rows can not be restored", table)
+            }
+            Error::NotFinite(value) => write!(f, "{} can not be used as default value", value),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
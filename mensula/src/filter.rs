@@ -1,48 +1,124 @@
 use std::{
     fmt::Display,
     marker::PhantomData,
-    ops::{BitAnd, BitOr},
+    ops::{BitAnd, BitOr, Not},
 };
 
+use serde::{Deserialize, Serialize};
 use sqlite::{Statement, Value};
 
-use crate::{Key, Table};
+use crate::{Error, Key, PrimaryKey, Result, Table};
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub enum FilterValue {
     Text(String),
     Int(i64),
+    Real(f64),
+    Blob(Vec<u8>),
     Null,
 }
 
 pub enum Filter<T: Table> {
+    /// Compared with `IS NULL` if the value is [`FilterValue::Null`]
     Eq(&'static str, FilterValue),
+    /// Compared with `IS NOT NULL` if the value is [`FilterValue::Null`]
+    Ne(&'static str, FilterValue),
+    Lt(&'static str, FilterValue),
+    Le(&'static str, FilterValue),
+    Gt(&'static str, FilterValue),
+    Ge(&'static str, FilterValue),
+    /// Inclusive on both ends
+    Between(&'static str, FilterValue, FilterValue),
     Like(&'static str, FilterValue),
+    IsNull(&'static str),
+    IsNotNull(&'static str),
     In {
         own_column_name: &'static str,
         other_column_name: &'static str,
         other_table_name: &'static str,
         filter: Box<Self>,
     },
+    /// Matches nothing if the list is empty
+    InValues(&'static str, Vec<FilterValue>),
     And(Box<Self>, Box<Self>),
     Or(Box<Self>, Box<Self>),
+    Not(Box<Self>),
     Phantom(PhantomData<T>),
 }
 
+impl FilterValue {
+    /// Formats the value as SQL literal, e.g. for a `DEFAULT` clause.
+    ///
+    /// Fails for `NaN` and infinite reals, as SQLite has no literal for them.
+    pub(crate) fn to_sql(&self) -> Result<String> {
+        let sql = match self {
+            FilterValue::Text(text) => format!("'{}'", text.replace('\'', "''")),
+            FilterValue::Int(int) => int.to_string(),
+            FilterValue::Real(real) if !real.is_finite() => return Err(Error::NotFinite(*real)),
+            FilterValue::Real(real) => format!("{:?}", real),
+            FilterValue::Blob(blob) => {
+                let hex: String = blob.iter().map(|byte| format!("{:02X}", byte)).collect();
+                format!("X'{}'", hex)
+            }
+            FilterValue::Null => "NULL".to_owned(),
+        };
+
+        Ok(sql)
+    }
+}
+
 impl From<String> for FilterValue {
     fn from(value: String) -> Self {
         Self::Text(value)
     }
 }
 
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_owned())
+    }
+}
+
 impl From<i64> for FilterValue {
     fn from(value: i64) -> Self {
         Self::Int(value)
     }
 }
 
-impl From<Key> for FilterValue {
-    fn from(value: Key) -> Self {
+impl From<f64> for FilterValue {
+    fn from(value: f64) -> Self {
+        Self::Real(value)
+    }
+}
+
+impl From<bool> for FilterValue {
+    fn from(value: bool) -> Self {
+        Self::Int(value.into())
+    }
+}
+
+impl From<Vec<u8>> for FilterValue {
+    fn from(value: Vec<u8>) -> Self {
+        Self::Blob(value)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::FixedOffset>> for FilterValue {
+    fn from(value: chrono::DateTime<chrono::FixedOffset>) -> Self {
+        Self::Text(value.to_rfc3339())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDate> for FilterValue {
+    fn from(value: chrono::NaiveDate) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl<T> From<Key<T>> for FilterValue {
+    fn from(value: Key<T>) -> Self {
         Self::Text(value.into())
     }
 }
@@ -56,11 +132,25 @@ impl<T: Into<FilterValue>> From<Option<T>> for FilterValue {
     }
 }
 
+impl From<sqlite::Value> for FilterValue {
+    fn from(value: sqlite::Value) -> Self {
+        match value {
+            sqlite::Value::String(text) => FilterValue::Text(text),
+            sqlite::Value::Integer(int) => FilterValue::Int(int),
+            sqlite::Value::Float(real) => FilterValue::Real(real),
+            sqlite::Value::Binary(blob) => FilterValue::Blob(blob),
+            sqlite::Value::Null => FilterValue::Null,
+        }
+    }
+}
+
 impl Into<sqlite::Value> for FilterValue {
     fn into(self) -> sqlite::Value {
         match self {
             FilterValue::Text(text) => sqlite::Value::String(text),
             FilterValue::Int(int) => sqlite::Value::Integer(int),
+            FilterValue::Real(real) => sqlite::Value::Float(real),
+            FilterValue::Blob(blob) => sqlite::Value::Binary(blob),
             FilterValue::Null => sqlite::Value::Null,
         }
     }
@@ -82,7 +172,39 @@ impl<T: Table> BitOr for Filter<T> {
     }
 }
 
+impl<T: Table> Not for Filter<T> {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self::Not(Box::new(self))
+    }
+}
+
 impl<T: Table> Filter<T> {
+    /// Matches the row with the primary key `key`
+    pub fn primary(key: T::Primary) -> Self {
+        T::primary_columns()
+            .into_iter()
+            .zip(key.into_values())
+            .map(|(column, value)| column.eq(value))
+            .reduce(Self::and)
+            .expect("a table has at least one primary column")
+    }
+
+    /// Matches the rows with any of the primary keys, nothing if there are none
+    pub fn primary_in(keys: impl IntoIterator<Item = T::Primary>) -> Self {
+        let columns = T::primary_columns();
+
+        if columns.len() == 1 {
+            return columns[0].in_values(keys.into_iter().flat_map(PrimaryKey::into_values));
+        }
+
+        keys.into_iter()
+            .map(Self::primary)
+            .reduce(Self::or)
+            .unwrap_or_else(|| Self::InValues(columns[0].name, Vec::new()))
+    }
+
     pub fn and(self, other: Self) -> Self {
         Self::And(Box::new(self), Box::new(other))
     }
@@ -91,41 +213,125 @@ impl<T: Table> Filter<T> {
         Self::Or(Box::new(self), Box::new(other))
     }
 
+    /// Keeps the column names, e.g. for the filter of a subquery on another table
+    pub(crate) fn cast<U: Table>(self) -> Filter<U> {
+        match self {
+            Filter::Eq(name, value) => Filter::Eq(name, value),
+            Filter::Ne(name, value) => Filter::Ne(name, value),
+            Filter::Lt(name, value) => Filter::Lt(name, value),
+            Filter::Le(name, value) => Filter::Le(name, value),
+            Filter::Gt(name, value) => Filter::Gt(name, value),
+            Filter::Ge(name, value) => Filter::Ge(name, value),
+            Filter::Between(name, low, high) => Filter::Between(name, low, high),
+            Filter::Like(name, value) => Filter::Like(name, value),
+            Filter::IsNull(name) => Filter::IsNull(name),
+            Filter::IsNotNull(name) => Filter::IsNotNull(name),
+            Filter::In {
+                own_column_name,
+                other_column_name,
+                other_table_name,
+                filter,
+            } => Filter::In {
+                own_column_name,
+                other_column_name,
+                other_table_name,
+                filter: Box::new(filter.cast()),
+            },
+            Filter::InValues(name, values) => Filter::InValues(name, values),
+            Filter::And(a, b) => Filter::And(Box::new(a.cast()), Box::new(b.cast())),
+            Filter::Or(a, b) => Filter::Or(Box::new(a.cast()), Box::new(b.cast())),
+            Filter::Not(filter) => Filter::Not(Box::new(filter.cast())),
+            Filter::Phantom(_) => Filter::Phantom(PhantomData),
+        }
+    }
+
     pub fn bind(self, statement: &mut Statement) -> sqlite::Result<()> {
         self.bind_counted(statement, &mut 0)
     }
 
-    fn bind_counted(self, statement: &mut Statement, counter: &mut usize) -> sqlite::Result<()> {
+    pub(crate) fn bind_counted(self, statement: &mut Statement, counter: &mut usize) -> sqlite::Result<()> {
         match self {
-            Filter::Eq(_, value) | Filter::Like(_, value) => {
-                *counter += 1;
-                statement.bind::<(_, Value)>((counter.to_owned(), value.into()))
+            Filter::Eq(_, FilterValue::Null) | Filter::Ne(_, FilterValue::Null) => Ok(()),
+            Filter::Eq(_, value)
+            | Filter::Ne(_, value)
+            | Filter::Lt(_, value)
+            | Filter::Le(_, value)
+            | Filter::Gt(_, value)
+            | Filter::Ge(_, value)
+            | Filter::Like(_, value) => bind_value(statement, counter, value),
+            Filter::Between(_, low, high) => {
+                bind_value(statement, counter, low)?;
+                bind_value(statement, counter, high)
+            }
+            Filter::IsNull(_) | Filter::IsNotNull(_) => Ok(()),
+            Filter::InValues(_, values) => {
+                for value in values {
+                    bind_value(statement, counter, value)?;
+                }
+                Ok(())
             }
             Filter::And(a, b) | Filter::Or(a, b) => {
                 a.bind_counted(statement, counter)?;
                 b.bind_counted(statement, counter)
             }
+            Filter::Not(filter) => filter.bind_counted(statement, counter),
             Filter::In { filter, .. } => filter.bind_counted(statement, counter),
             Filter::Phantom(_) => Ok(()),
         }
     }
 }
 
+fn bind_value(statement: &mut Statement, counter: &mut usize, value: FilterValue) -> sqlite::Result<()> {
+    *counter += 1;
+    statement.bind::<(_, Value)>((counter.to_owned(), value.into()))
+}
+
 impl<T: Table> Display for Filter<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Filter::Eq(name, FilterValue::Null) | Filter::IsNull(name) => {
+                write!(f, "{} IS NULL", name)
+            }
+            Filter::Ne(name, FilterValue::Null) | Filter::IsNotNull(name) => {
+                write!(f, "{} IS NOT NULL", name)
+            }
             Filter::Eq(name, _) => {
                 write!(f, "{} = ?", name)
             }
+            Filter::Ne(name, _) => {
+                write!(f, "{} != ?", name)
+            }
+            Filter::Lt(name, _) => {
+                write!(f, "{} < ?", name)
+            }
+            Filter::Le(name, _) => {
+                write!(f, "{} <= ?", name)
+            }
+            Filter::Gt(name, _) => {
+                write!(f, "{} > ?", name)
+            }
+            Filter::Ge(name, _) => {
+                write!(f, "{} >= ?", name)
+            }
+            Filter::Between(name, _, _) => {
+                write!(f, "{} BETWEEN ? AND ?", name)
+            }
             Filter::Like(name, _) => {
                 write!(f, "{} LIKE ?", name)
             }
+            Filter::InValues(name, values) => {
+                let placeholders = vec!["?"; values.len()].join(", ");
+                write!(f, "{} IN ({})", name, placeholders)
+            }
             Filter::And(a, b) => {
                 write!(f, "({}) AND ({})", a, b)
             }
             Filter::Or(a, b) => {
                 write!(f, "({}) OR ({})", a, b)
             }
+            Filter::Not(filter) => {
+                write!(f, "NOT ({})", filter)
+            }
             Filter::In {
                 own_column_name,
                 other_column_name,
@@ -1,24 +1,52 @@
 mod database;
+mod error;
 pub mod query;
 mod table;
 mod filter;
 mod meta;
+mod transaction;
+mod pool;
+mod statement_cache;
+mod logger;
+mod backup;
+mod observer;
 pub use mensula_key as key;
 
+// The derives refer to `mensula::`, which has to resolve in the tests of this crate as well
+#[cfg(test)]
+extern crate self as mensula;
+
 pub use table::DataType;
 pub use table::AsDataType;
 pub use database::Database;
+pub use pool::Pool;
+pub use pool::PooledDatabase;
+pub use statement_cache::StatementCacheStats;
+pub use logger::QueryInfo;
+pub use logger::MigrationInfo;
+pub use logger::QueryLogger;
+pub use logger::SlowQueryLogger;
+pub use error::Error;
+pub use error::Result;
 pub use table::modifier::Modifier;
 pub use table::modifier::ForeignReference;
 pub use table::modifier::ForeignRule;
 pub use table::Table;
 pub use table::Readable;
+pub use table::FromRow;
+pub use table::FromValue;
+pub use table::Row;
 pub use table::Insertable;
 pub use table::Column;
+pub use table::Index;
+pub use table::PrimaryKey;
 pub use table::Link;
 pub use filter::Filter;
 pub use filter::FilterValue;
 
 pub use sqlite;
 pub use mensula_derive::Table;
+pub use mensula_derive::FromRow;
+pub use mensula_derive::AsDataType;
+pub use mensula_derive::Projection;
 pub use key::Key;
\ No newline at end of file
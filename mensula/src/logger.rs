@@ -0,0 +1,81 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+/// A statement that was run on a [`crate::Database`].
+#[derive(Clone, Debug)]
+pub struct QueryInfo<'a> {
+    pub sql: &'a str,
+    /// The number of parameters of the statement
+    pub parameters: usize,
+    /// The time spent executing the statement, without reading the rows
+    pub duration: Duration,
+    /// The number of rows returned by a select or changed by any other statement
+    pub rows: usize,
+}
+
+/// A table that was migrated to a new schema by [`crate::Database::register`].
+#[derive(Clone, Debug)]
+pub struct MigrationInfo<'a> {
+    pub table: &'a str,
+    /// The primary columns before and after the migration, if they changed
+    pub primary: Option<(&'a str, &'a str)>,
+    pub added: Vec<&'a str>,
+    pub dropped: Vec<&'a str>,
+    /// The columns whose type, constraints or default changed
+    pub changed: Vec<&'a str>,
+}
+
+impl Display for MigrationInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "migrated table '{}'", self.table)?;
+
+        if let Some((before, after)) = self.primary {
+            write!(f, ", changed primary key from ({}) to ({})", before, after)?;
+        }
+
+        for (action, columns) in [("added", &self.added), ("dropped", &self.dropped), ("changed", &self.changed)] {
+            if !columns.is_empty() {
+                write!(f, ", {} {}", action, columns.join(", "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Receives every statement run on a [`crate::Database`] after it finished.
+///
+/// Set with [`crate::Database::set_logger`] or [`crate::Pool::set_logger`].
+pub trait QueryLogger: Send + Sync {
+    fn log(&self, query: &QueryInfo);
+
+    /// Called after a table was migrated, does nothing by default.
+    fn log_migration(&self, _migration: &MigrationInfo) {}
+}
+
+/// Prints every statement that took at least `threshold` and every migration to stderr.
+#[derive(Clone, Debug)]
+pub struct SlowQueryLogger {
+    threshold: Duration,
+}
+
+impl SlowQueryLogger {
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold }
+    }
+}
+
+impl QueryLogger for SlowQueryLogger {
+    fn log(&self, query: &QueryInfo) {
+        if query.duration >= self.threshold {
+            eprintln!(
+                "slow query took {:?} ({} parameters, {} rows): {}",
+                query.duration, query.parameters, query.rows, query.sql
+            );
+        }
+    }
+
+    fn log_migration(&self, migration: &MigrationInfo) {
+        eprintln!("{}", migration);
+    }
+}
@@ -1,8 +1,11 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
+use sqlite::State;
 
-use crate::{table::DataTypeKind, Table};
+use crate::{table::DataTypeKind, Database, Error, FilterValue, Result, Table};
+
+const META_TABLE_NAME: &str = "_mensula_meta";
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Meta {
@@ -23,18 +26,40 @@ impl Default for Meta {
 pub struct MetaTable {
     primary: String,
     columns: HashMap<String, MetaColumn>,
+    #[serde(default)]
+    indices: HashMap<String, MetaIndex>,
+    #[serde(default)]
+    fulltext: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct MetaColumn {
-    data_type: DataTypeKind,
-    optional: bool,
-    unique: bool,
+    pub data_type: DataTypeKind,
+    pub optional: bool,
+    pub unique: bool,
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub check: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct MetaIndex {
+    pub columns: Vec<String>,
+    pub unique: bool,
 }
 
 pub enum Difference {
     NewTable,
+    /// The primary key changed, which requires the table to be rebuilt
+    Primary {
+        before: String,
+        after: String,
+        columns: HashMap<String, ColumnDifference>,
+    },
     Columns(HashMap<String, ColumnDifference>),
+    /// Only the indices or full-text columns changed
+    Indices,
 }
 
 #[derive(Debug)]
@@ -44,28 +69,112 @@ pub struct ColumnDifference {
 }
 
 impl Meta {
-    pub fn save<P: AsRef<Path>>(&self, path: P) -> bool {
-        let content = match toml::to_string(self) {
-            Ok(content) => content,
-            Err(_) => return false,
-        };
-        fs::write(path, content).is_ok()
+    /// Reads the meta of all registered tables from the meta table, creating it if necessary.
+    pub fn load(database: &Database) -> Result<Self> {
+        database.execute(format!(
+            "CREATE TABLE IF NOT EXISTS {} (table_name TEXT PRIMARY KEY NOT NULL, meta TEXT NOT NULL)",
+            META_TABLE_NAME
+        ))?;
+
+        let mut statement =
+            database.prepare(format!("SELECT table_name, meta FROM {}", META_TABLE_NAME))?;
+
+        let mut meta = Self::default();
+
+        while let State::Row = statement.next()? {
+            let name: String = statement.read("table_name")?;
+            let table: String = statement.read("meta")?;
+
+            let table = toml::from_str(&table).map_err(|_| Error::Decode {
+                column: format!("{}.meta", META_TABLE_NAME),
+            })?;
+
+            meta.tables.insert(name, table);
+        }
+
+        Ok(meta)
+    }
+
+    /// Writes the meta of all tables, used to import a `meta.toml` file.
+    pub fn store(&self, database: &Database) -> Result<()> {
+        for (name, table) in &self.tables {
+            Self::store_meta_table(database, name, table)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the current meta of `T`.
+    ///
+    /// Should run in the same transaction as the statements changing the table.
+    pub fn store_table<T: Table>(database: &Database) -> Result<()> {
+        Self::store_meta_table(database, T::table_name(), &Self::get_meta_table::<T>()?)
+    }
+
+    fn store_meta_table(database: &Database, name: &str, table: &MetaTable) -> Result<()> {
+        let meta = toml::to_string(table).map_err(|err| Error::Migration(err.to_string()))?;
+
+        let mut statement = database.prepare(format!(
+            "INSERT INTO {} (table_name, meta) VALUES (?, ?)
+            ON CONFLICT (table_name) DO UPDATE SET meta = excluded.meta",
+            META_TABLE_NAME
+        ))?;
+
+        statement.bind((1, name))?;
+        statement.bind((2, meta.as_str()))?;
+
+        statement.next()?;
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
     }
 
-    pub fn get_difference<T: Table>(&self) -> Option<Difference> {
+    /// Fails if a default value of `T` can not be written as SQL literal.
+    pub fn get_difference<T: Table>(&self) -> Result<Option<Difference>> {
         let name = T::table_name();
+        let meta_table = Self::get_meta_table::<T>()?;
 
-        if let Some(table) = self.get_table(name) {
-            let difference = table.compare(&Self::get_meta_table::<T>());
+        let difference = if let Some(table) = self.get_table(name) {
+            let difference = table.compare(&meta_table);
 
-            if difference.is_empty() {
-                None
-            } else {
+            if table.primary != meta_table.primary {
+                Some(Difference::Primary {
+                    before: table.primary.to_owned(),
+                    after: meta_table.primary,
+                    columns: difference,
+                })
+            } else if !difference.is_empty() {
                 Some(Difference::Columns(difference))
+            } else if table.indices != meta_table.indices || table.fulltext != meta_table.fulltext {
+                Some(Difference::Indices)
+            } else {
+                None
             }
         } else {
             Some(Difference::NewTable)
-        }
+        };
+
+        Ok(difference)
+    }
+
+    /// The names of the indices that exist in the database but were removed from `T`.
+    pub fn get_dropped_indices<T: Table>(&self) -> Result<Vec<String>> {
+        let meta_table = Self::get_meta_table::<T>()?;
+
+        let dropped = match self.get_table(T::table_name()) {
+            Some(table) => table
+                .indices
+                .keys()
+                .filter(|name| !meta_table.indices.contains_key(*name))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Ok(dropped)
     }
 
     pub fn has_table<T: Table>(&self) -> bool {
@@ -76,29 +185,68 @@ impl Meta {
         self.tables.get(name)
     }
 
-    fn get_meta_table<T: Table>() -> MetaTable {
+    fn get_meta_table<T: Table>() -> Result<MetaTable> {
         let column_iter = T::get_columns().into_iter().map(|col| {
-            (
+            Ok((
                 col.name.to_owned(),
                 MetaColumn {
                     data_type: col.data_type.data_type,
                     optional: col.data_type.optional,
                     unique: col.modifier.unique,
+                    default: col.modifier.default.as_ref().map(FilterValue::to_sql).transpose()?,
+                    check: col.modifier.check,
+                },
+            ))
+        });
+
+        let columns = column_iter.collect::<Result<HashMap<_, _>>>()?;
+
+        let index_iter = T::get_indices().into_iter().map(|index| {
+            (
+                index.name(T::table_name()),
+                MetaIndex {
+                    columns: index.columns.iter().map(|name| name.to_string()).collect(),
+                    unique: index.unique,
                 },
             )
         });
 
-        let columns = HashMap::from_iter(column_iter);
+        let indices = HashMap::from_iter(index_iter);
 
-        MetaTable {
-            primary: T::primary_column().name.to_owned(),
+        let fulltext = T::get_fulltext_columns()
+            .into_iter()
+            .map(|name| name.to_owned())
+            .collect();
+
+        Ok(MetaTable {
+            primary: T::primary_columns()
+                .iter()
+                .map(|column| column.name)
+                .collect::<Vec<_>>()
+                .join(", "),
             columns,
-        }
+            indices,
+            fulltext,
+        })
     }
 
-    pub fn update_table<T: Table>(&mut self) {
+    pub fn update_table<T: Table>(&mut self) -> Result<()> {
         self.tables
-            .insert(T::table_name().to_owned(), Self::get_meta_table::<T>());
+            .insert(T::table_name().to_owned(), Self::get_meta_table::<T>()?);
+        Ok(())
+    }
+}
+
+impl ColumnDifference {
+    /// Whether this change can be applied with a plain `ALTER TABLE ADD COLUMN`.
+    ///
+    /// SQLite only allows adding columns that are nullable or have a default and are not unique,
+    /// everything else requires the table to be rebuilt.
+    pub fn is_addable(&self) -> bool {
+        match (&self.before, &self.after) {
+            (None, Some(after)) => (after.optional || after.default.is_some()) && !after.unique,
+            _ => false,
+        }
     }
 }
 
@@ -0,0 +1,179 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::query::SelectQuery;
+use crate::{Database, Filter, PrimaryKey, Readable, Result, Table};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// Rows of a table changed by a single statement, which are reported once they are committed.
+pub(crate) struct Change {
+    table_name: &'static str,
+    kind: ChangeKind,
+    /// The `Vec<T::Primary>` of the changed rows
+    keys: Box<dyn Any + Send>,
+}
+
+impl Change {
+    pub(crate) fn new<T: Table>(kind: ChangeKind, keys: Vec<T::Primary>) -> Self {
+        Self {
+            table_name: T::table_name(),
+            kind,
+            keys: Box::new(keys),
+        }
+    }
+}
+
+/// The keys of a change, which always has the key type of the table the observer was added for.
+fn keys<T: Table>(keys: &dyn Any) -> Vec<T::Primary> {
+    keys.downcast_ref::<Vec<T::Primary>>()
+        .expect("changes are recorded with the primary key type of their table")
+        .clone()
+}
+
+/// A call to an observer, prepared before the changes are committed.
+pub(crate) type Notification<'a> = Box<dyn FnOnce() + 'a>;
+
+trait Observer: Send + Sync {
+    /// Reads everything the observer needs while the changes are still visible to the transaction.
+    fn prepare<'a>(&'a self, database: &Database, keys: &dyn Any) -> Result<Notification<'a>>;
+}
+
+/// The lowest limit of SQLite on the number of parameters of a statement
+const MAX_PARAMETERS: usize = 999;
+
+/// Calls `f` with the keys and the current rows, the row of each key at the same position.
+struct RowObserver<T, F> {
+    f: F,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T, F> Observer for RowObserver<T, F>
+where
+    T: Table + Readable<T>,
+    F: Fn(&[T::Primary], &[T]) + Send + Sync,
+{
+    fn prepare<'a>(&'a self, database: &Database, keys: &dyn Any) -> Result<Notification<'a>> {
+        let chunk_size = MAX_PARAMETERS / T::primary_columns().len();
+
+        let mut found_keys = Vec::new();
+        let mut rows = Vec::new();
+
+        for chunk in self::keys::<T>(keys).chunks(chunk_size) {
+            let mut chunk_rows = SelectQuery::<T>::new()
+                .with_deleted()
+                .filter(Filter::primary_in(chunk.iter().cloned()))
+                .get_all_with_keys::<T>(database)?
+                .into_iter()
+                .map(|(key, row)| (key.into_values(), row))
+                .collect::<Vec<_>>();
+
+            // Keys are matched by their values, as composite keys do not need to be hashable
+            for key in chunk {
+                let values = key.clone().into_values();
+
+                if let Some(index) = chunk_rows.iter().position(|(row_key, _)| *row_key == values) {
+                    found_keys.push(key.clone());
+                    rows.push(chunk_rows.swap_remove(index).1);
+                }
+            }
+        }
+
+        Ok(Box::new(move || (self.f)(&found_keys, &rows)))
+    }
+}
+
+/// Calls `f` with the keys only.
+struct KeyObserver<T, F> {
+    f: F,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T, F> Observer for KeyObserver<T, F>
+where
+    T: Table,
+    F: Fn(&[T::Primary]) + Send + Sync,
+{
+    fn prepare<'a>(&'a self, _: &Database, keys: &dyn Any) -> Result<Notification<'a>> {
+        let keys = self::keys::<T>(keys);
+
+        Ok(Box::new(move || (self.f)(&keys)))
+    }
+}
+
+/// The observers of a [`Database`], by table and kind of change.
+#[derive(Clone, Default)]
+pub(crate) struct Observers {
+    observers: HashMap<(&'static str, ChangeKind), Vec<Arc<dyn Observer>>>,
+}
+
+impl Observers {
+    pub(crate) fn on_insert<T, F>(&mut self, f: F)
+    where
+        T: Table + Readable<T> + 'static,
+        F: Fn(&[T::Primary], &[T]) + Send + Sync + 'static,
+    {
+        self.add::<T>(ChangeKind::Insert, RowObserver { f, phantom: PhantomData });
+    }
+
+    pub(crate) fn on_update<T, F>(&mut self, f: F)
+    where
+        T: Table + Readable<T> + 'static,
+        F: Fn(&[T::Primary], &[T]) + Send + Sync + 'static,
+    {
+        self.add::<T>(ChangeKind::Update, RowObserver { f, phantom: PhantomData });
+    }
+
+    pub(crate) fn on_delete<T, F>(&mut self, f: F)
+    where
+        T: Table + 'static,
+        F: Fn(&[T::Primary]) + Send + Sync + 'static,
+    {
+        self.add::<T>(ChangeKind::Delete, KeyObserver::<T, F> { f, phantom: PhantomData });
+    }
+
+    fn add<T: Table>(&mut self, kind: ChangeKind, observer: impl Observer + 'static) {
+        self.observers
+            .entry((T::table_name(), kind))
+            .or_default()
+            .push(Arc::new(observer));
+    }
+
+    /// Whether the keys of changes of this kind need to be collected.
+    pub(crate) fn is_observed<T: Table>(&self, kind: ChangeKind) -> bool {
+        self.observers.contains_key(&(T::table_name(), kind))
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /// Prepares the calls of all observers of `changes`, in the order the changes were made.
+    pub(crate) fn prepare(
+        &self,
+        database: &Database,
+        changes: Vec<Change>,
+    ) -> Result<Vec<Notification<'_>>> {
+        let mut notifications = Vec::new();
+
+        for change in changes {
+            let observers = match self.observers.get(&(change.table_name, change.kind)) {
+                Some(observers) => observers,
+                None => continue,
+            };
+
+            for observer in observers {
+                notifications.push(observer.prepare(database, &*change.keys)?);
+            }
+        }
+
+        Ok(notifications)
+    }
+}
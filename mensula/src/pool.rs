@@ -0,0 +1,215 @@
+use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::observer::Observers;
+use crate::{Database, QueryLogger, Readable, Result, Table};
+
+/// How long a connection waits for the lock of another writer before failing
+const BUSY_TIMEOUT_MS: usize = 5000;
+
+/// A pool of connections to the same database file.
+///
+/// The database is switched to WAL mode, so any number of connections can read
+/// while one of them is writing. Connections are opened lazily up to `max_size`,
+/// after which [`Pool::get`] waits for a connection to be returned.
+///
+/// Tables should be registered on the first connection before the pool is shared,
+/// because every connection keeps its own copy of the meta.
+///
+/// The pool is a handle that can be cloned cheaply, all clones share the same connections.
+#[derive(Clone)]
+pub struct Pool {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    path: PathBuf,
+    max_size: usize,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+struct PoolState {
+    idle: Vec<Database>,
+    open: usize,
+    /// Applied to every connection when it is checked out
+    logger: Option<Arc<dyn QueryLogger>>,
+    /// Applied to every connection when it is checked out
+    observers: Arc<Observers>,
+}
+
+/// A connection checked out of a [`Pool`], which is returned when dropped.
+pub struct PooledDatabase {
+    database: Option<Database>,
+    pool: Pool,
+}
+
+impl Pool {
+    pub fn open<P: AsRef<Path>>(path: P, max_size: usize) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let database = Self::connect(&path)?;
+
+        Ok(Self::new(path, max_size.max(1), database))
+    }
+
+    /// Creates a pool with a single in-memory connection, e.g. for tests.
+    ///
+    /// Every connection to `:memory:` is a separate database, so the pool never opens another one.
+    pub fn open_in_memory() -> Result<Self> {
+        Ok(Self::new(
+            PathBuf::from(":memory:"),
+            1,
+            Database::open_in_memory()?,
+        ))
+    }
+
+    fn new(path: PathBuf, max_size: usize, database: Database) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                path,
+                max_size,
+                state: Mutex::new(PoolState {
+                    idle: vec![database],
+                    open: 1,
+                    logger: None,
+                    observers: Arc::default(),
+                }),
+                available: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Passes every statement run on any connection of the pool to `logger`.
+    ///
+    /// Connections that are checked out use the logger once they are checked out again.
+    pub fn set_logger<L: QueryLogger + 'static>(&self, logger: L) {
+        self.shared.state.lock().unwrap().logger = Some(Arc::new(logger));
+    }
+
+    /// See [`Database::on_insert`], applies to every connection of the pool
+    /// once it is checked out the next time.
+    pub fn on_insert<T, F>(&self, f: F)
+    where
+        T: Table + Readable<T> + 'static,
+        F: Fn(&[T::Primary], &[T]) + Send + Sync + 'static,
+    {
+        self.update_observers(|observers| observers.on_insert(f));
+    }
+
+    /// See [`Database::on_update`], applies to every connection of the pool
+    /// once it is checked out the next time.
+    pub fn on_update<T, F>(&self, f: F)
+    where
+        T: Table + Readable<T> + 'static,
+        F: Fn(&[T::Primary], &[T]) + Send + Sync + 'static,
+    {
+        self.update_observers(|observers| observers.on_update(f));
+    }
+
+    /// See [`Database::on_delete`], applies to every connection of the pool
+    /// once it is checked out the next time.
+    pub fn on_delete<T, F>(&self, f: F)
+    where
+        T: Table + 'static,
+        F: Fn(&[T::Primary]) + Send + Sync + 'static,
+    {
+        self.update_observers(|observers| observers.on_delete::<T, F>(f));
+    }
+
+    fn update_observers(&self, f: impl FnOnce(&mut Observers)) {
+        let mut state = self.shared.state.lock().unwrap();
+
+        f(Arc::make_mut(&mut state.observers));
+    }
+
+    fn connect(path: &Path) -> Result<Database> {
+        let database = Database::open(path)?;
+
+        database.execute("PRAGMA journal_mode = WAL")?;
+        database.execute(format!("PRAGMA busy_timeout = {}", BUSY_TIMEOUT_MS))?;
+
+        Ok(database)
+    }
+
+    /// Checks out an idle connection, opens a new one or waits until one is returned.
+    pub fn get(&self) -> Result<PooledDatabase> {
+        let shared = &self.shared;
+        let mut state = shared.state.lock().unwrap();
+
+        loop {
+            if let Some(database) = state.idle.pop() {
+                return Ok(self.check_out(database, &state));
+            }
+
+            if state.open < shared.max_size {
+                state.open += 1;
+                drop(state);
+
+                return match Self::connect(&shared.path) {
+                    Ok(database) => Ok(self.check_out(database, &shared.state.lock().unwrap())),
+                    Err(err) => {
+                        shared.state.lock().unwrap().open -= 1;
+                        shared.available.notify_one();
+                        Err(err)
+                    }
+                };
+            }
+
+            state = shared.available.wait(state).unwrap();
+        }
+    }
+
+    /// Applies the current logger and observers, which may have changed since the connection was last used.
+    fn check_out(&self, mut database: Database, state: &PoolState) -> PooledDatabase {
+        database.set_shared_logger(state.logger.clone());
+        database.set_observers(state.observers.clone());
+
+        PooledDatabase {
+            database: Some(database),
+            pool: self.clone(),
+        }
+    }
+
+    fn release(&self, database: Database) {
+        self.shared.state.lock().unwrap().idle.push(database);
+        self.shared.available.notify_one();
+    }
+}
+
+impl Debug for Pool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.shared.state.lock().unwrap();
+
+        f.debug_struct("Pool")
+            .field("path", &self.shared.path)
+            .field("max_size", &self.shared.max_size)
+            .field("logger", &state.logger.is_some())
+            .field("idle", &state.idle.len())
+            .field("open", &state.open)
+            .finish()
+    }
+}
+
+impl Deref for PooledDatabase {
+    type Target = Database;
+
+    fn deref(&self) -> &Self::Target {
+        self.database.as_ref().expect("database already released")
+    }
+}
+
+impl DerefMut for PooledDatabase {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.database.as_mut().expect("database already released")
+    }
+}
+
+impl Drop for PooledDatabase {
+    fn drop(&mut self) {
+        if let Some(database) = self.database.take() {
+            self.pool.release(database);
+        }
+    }
+}
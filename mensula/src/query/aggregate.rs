@@ -0,0 +1,218 @@
+use std::{fmt::Display, marker::PhantomData};
+
+use sqlite::State;
+
+use crate::{filter::Filter, query::Ordering, Column, Database, FromRow, Result, Table};
+
+use super::select::Deleted;
+
+enum Selection {
+    Column(&'static str),
+    /// The first characters of a column
+    Prefix(&'static str, usize),
+    Count,
+    Sum(&'static str),
+    Min(&'static str),
+    Max(&'static str),
+    Avg(&'static str),
+}
+
+impl Display for Selection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Selection::Column(name) => write!(f, "{}", name),
+            Selection::Prefix(name, length) => write!(f, "substr({}, 1, {})", name, length),
+            Selection::Count => write!(f, "COUNT(*)"),
+            Selection::Sum(name) => write!(f, "SUM({})", name),
+            Selection::Min(name) => write!(f, "MIN({})", name),
+            Selection::Max(name) => write!(f, "MAX({})", name),
+            Selection::Avg(name) => write!(f, "AVG({})", name),
+        }
+    }
+}
+
+/// Selects columns and aggregates of a table, optionally grouped by some columns.
+///
+/// The selections are read in the order they were added, so the result can be read
+/// into a tuple or a struct deriving `FromRow` with the fields in the same order.
+/// Deleted rows of a table with `#[soft_delete]` are excluded like in a `SelectQuery`.
+pub struct AggregateQuery<T: Table> {
+    selections: Vec<Selection>,
+    filter: Option<Filter<T>>,
+    group_by: Vec<Selection>,
+    ordering: Option<(&'static str, Ordering)>,
+    limit: Option<usize>,
+    deleted: Deleted,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Table> Default for AggregateQuery<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Table> AggregateQuery<T> {
+    pub fn new() -> Self {
+        Self {
+            selections: Vec::new(),
+            filter: None,
+            group_by: Vec::new(),
+            ordering: None,
+            limit: None,
+            deleted: Deleted::Exclude,
+            phantom: PhantomData,
+        }
+    }
+
+    // Selections
+
+    pub fn column(mut self, column: Column<T>) -> Self {
+        self.selections.push(Selection::Column(column.name));
+        self
+    }
+
+    /// The first `length` characters of `column`, e.g. the year and month of a timestamp.
+    pub fn prefix(mut self, column: Column<T>, length: usize) -> Self {
+        self.selections.push(Selection::Prefix(column.name, length));
+        self
+    }
+
+    pub fn count(mut self) -> Self {
+        self.selections.push(Selection::Count);
+        self
+    }
+
+    /// `NULL` if no row matches, so read it into an `Option`.
+    pub fn sum(mut self, column: Column<T>) -> Self {
+        self.selections.push(Selection::Sum(column.name));
+        self
+    }
+
+    pub fn min(mut self, column: Column<T>) -> Self {
+        self.selections.push(Selection::Min(column.name));
+        self
+    }
+
+    pub fn max(mut self, column: Column<T>) -> Self {
+        self.selections.push(Selection::Max(column.name));
+        self
+    }
+
+    /// Always a floating point number.
+    pub fn avg(mut self, column: Column<T>) -> Self {
+        self.selections.push(Selection::Avg(column.name));
+        self
+    }
+
+    // Builders
+
+    pub fn filter(mut self, filter: Filter<T>) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn group_by(mut self, column: Column<T>) -> Self {
+        self.group_by.push(Selection::Column(column.name));
+        self
+    }
+
+    /// Groups by the first `length` characters of `column`, select them with [`Self::prefix`].
+    pub fn group_by_prefix(mut self, column: Column<T>, length: usize) -> Self {
+        self.group_by.push(Selection::Prefix(column.name, length));
+        self
+    }
+
+    pub fn order_by(mut self, column: Column<T>, ordering: Ordering) -> Self {
+        self.ordering = Some((column.name, ordering));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Also aggregate deleted rows of a table with `#[soft_delete]`, which are excluded by default.
+    pub fn with_deleted(mut self) -> Self {
+        self.deleted = Deleted::Include;
+        self
+    }
+
+    /// Only aggregate deleted rows of a table with `#[soft_delete]`.
+    pub fn only_deleted(mut self) -> Self {
+        self.deleted = Deleted::Only;
+        self
+    }
+
+    // Runners
+
+    pub fn get_query(&self) -> String {
+        let selections = self
+            .selections
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut query = format!("SELECT {} FROM {}", selections, T::table_name());
+
+        let conditions = [
+            self.filter.as_ref().map(ToString::to_string),
+            self.deleted.condition::<T>(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|condition| format!("({})", condition))
+        .collect::<Vec<_>>();
+
+        if !conditions.is_empty() {
+            query += format!(" WHERE {}", conditions.join(" AND ")).as_str();
+        }
+
+        if !self.group_by.is_empty() {
+            let group_by = self
+                .group_by
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            query += format!(" GROUP BY {}", group_by).as_str();
+        }
+
+        if let Some((order_by, ordering)) = &self.ordering {
+            query += format!(" ORDER BY {} {}", order_by, ordering).as_str();
+        }
+
+        if let Some(limit) = self.limit {
+            query += format!(" LIMIT {}", limit).as_str();
+        }
+
+        query
+    }
+
+    pub fn get_all<R: FromRow>(self, database: &Database) -> Result<Vec<R>> {
+        let q = self.get_query();
+
+        let mut statement = database.prepare(q)?;
+
+        if let Some(filter) = self.filter {
+            filter.bind(&mut statement)?;
+        }
+
+        let mut data = Vec::new();
+
+        while let State::Row = statement.next()? {
+            data.push(R::from_row(&statement.row())?);
+        }
+
+        Ok(data)
+    }
+
+    pub fn get_first<R: FromRow>(mut self, database: &Database) -> Result<Option<R>> {
+        self.limit = Some(1);
+
+        Ok(self.get_all(database)?.into_iter().next())
+    }
+}
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::{Database, Table};
+use crate::{Database, Result, Table};
 
 pub struct CreateTableQuery<T: Table> {
     phantom: PhantomData<T>,
@@ -13,16 +13,30 @@ impl<T: Table> CreateTableQuery<T> {
         }
     }
 
-    pub fn run(self, database: &Database) -> sqlite::Result<()> {
-        let columns: Vec<String> = T::get_columns().iter().map(|c| c.to_string()).collect();
-        let columns = columns.join(", ");
+    pub(crate) fn get_query(&self, table_name: &str) -> String {
+        let primary_names: Vec<&str> = T::primary_columns().iter().map(|c| c.name).collect();
+        let is_composite = primary_names.len() > 1;
 
-        let q = format!(
-            "CREATE TABLE IF NOT EXISTS {} ({})",
-            T::table_name(),
-            columns
-        );
+        let columns: Vec<String> = T::get_columns()
+            .into_iter()
+            .map(|mut c| {
+                // A composite primary key can only be declared as table constraint
+                if is_composite {
+                    c.modifier.primary = false;
+                }
+                c.to_string()
+            })
+            .collect();
+        let mut columns = columns.join(", ");
 
-        database.execute(q)
+        if is_composite {
+            columns += format!(", PRIMARY KEY ({})", primary_names.join(", ")).as_str();
+        }
+
+        format!("CREATE TABLE IF NOT EXISTS {} ({})", table_name, columns)
+    }
+
+    pub fn run(self, database: &Database) -> Result<()> {
+        database.execute(self.get_query(T::table_name()))
     }
 }
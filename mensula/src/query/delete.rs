@@ -1,35 +1,97 @@
 use std::marker::PhantomData;
 
-use mensula_key::Key;
+use sqlite::State;
 
-use crate::{Database, Table};
+use crate::{
+  filter::Filter,
+  observer::ChangeKind,
+  table::{primary_names, DELETED_AT_COLUMN},
+  Database, PrimaryKey, Result, Table,
+};
 
+/// Deletes rows of a table.
+///
+/// Rows of a table with `#[soft_delete]` are only marked as deleted,
+/// unless the query is made [`DeleteQuery::permanent`].
 pub struct DeleteQuery<T: Table> {
-  id: Key,
+  filter: Filter<T>,
+  permanent: bool,
   phantom: PhantomData<T>,
 }
 
 impl<T: Table> DeleteQuery<T> {
-  pub fn new(id: Key) -> Self {
+  /// Delete the row with the primary key `id`, a tuple for a composite primary key.
+  pub fn new(id: T::Primary) -> Self {
+    Self::filter(Filter::primary(id))
+  }
+
+  /// Delete all rows matching `filter` instead of a single row.
+  pub fn filter(filter: Filter<T>) -> Self {
     Self {
-      id,
+      filter,
+      permanent: false,
       phantom: PhantomData,
     }
   }
 
-  pub fn run(&self, database: &Database) -> sqlite::Result<()> {
-    let q = format!(
-      "DELETE FROM {} WHERE {} = :id",
-      T::table_name(),
-      T::primary_column().name
-    );
+  /// Remove the rows even if the table has `#[soft_delete]`.
+  pub fn permanent(mut self) -> Self {
+    self.permanent = true;
+    self
+  }
+
+  fn get_query(&self) -> String {
+    let condition = self.filter.to_string();
+
+    if T::is_soft_delete() && !self.permanent {
+      format!(
+        "UPDATE {table} SET {deleted_at} = CURRENT_TIMESTAMP WHERE {deleted_at} IS NULL AND ({condition})",
+        table = T::table_name(),
+        deleted_at = DELETED_AT_COLUMN,
+        condition = condition,
+      )
+    } else {
+      format!("DELETE FROM {} WHERE {}", T::table_name(), condition)
+    }
+  }
+
+  /// Returns the number of deleted rows.
+  pub fn run(self, database: &Database) -> Result<usize> {
+    if database.needs_implicit_transaction::<T>(ChangeKind::Delete) {
+      return database.transaction(|transaction| self.run(transaction));
+    }
+
+    // The keys of the deleted rows are only needed by observers
+    let is_observed = database.is_observed::<T>(ChangeKind::Delete);
+
+    let mut q = self.get_query();
+
+    if is_observed {
+      q += format!(" RETURNING {}", primary_names::<T>()).as_str();
+    }
 
     let mut statement = database.prepare(q)?;
 
-    statement.bind((":id", self.id.clone()))?;
+    self.filter.bind(&mut statement)?;
+
+    if !is_observed {
+      statement.next()?;
+
+      return Ok(database.change_count());
+    }
+
+    let mut keys = Vec::new();
+
+    while let State::Row = statement.next()? {
+      keys.push(T::Primary::read(&statement.row())?);
+    }
+
+    drop(statement);
+
+    let count = keys.len();
 
-    statement.next()?;
+    database.record_change::<T>(ChangeKind::Delete, keys);
 
-    Ok(())
+    Ok(count)
   }
 }
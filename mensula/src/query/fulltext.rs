@@ -0,0 +1,259 @@
+use std::marker::PhantomData;
+
+use sqlite::State;
+
+use crate::{table::DELETED_AT_COLUMN, Database, Filter, FromRow, Result, Table};
+
+/// The name of the FTS5 table that indexes the full-text columns of `table_name`
+pub(crate) fn fulltext_table_name(table_name: &str) -> String {
+    format!("{}_fts", table_name)
+}
+
+/// Recreates the FTS5 table of the full-text columns of the table
+/// and the triggers that keep it in sync with the table.
+///
+/// The FTS5 table stores a copy of the primary key in `UNINDEXED` columns and is joined on them,
+/// because the `rowid` of the table is not stable, e.g. `VACUUM` may renumber it.
+/// It is rebuilt from the table every time, e.g. after the table was rebuilt by a migration.
+pub struct FulltextQuery<T: Table> {
+    phantom: PhantomData<T>,
+}
+
+impl<T: Table> Default for FulltextQuery<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Table> FulltextQuery<T> {
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn run(self, database: &Database) -> Result<()> {
+        let table_name = T::table_name();
+        let fts_name = fulltext_table_name(table_name);
+
+        for trigger in ["insert", "delete", "update"] {
+            database.execute(format!("DROP TRIGGER IF EXISTS {}_{}", fts_name, trigger))?;
+        }
+
+        database.execute(format!("DROP TABLE IF EXISTS {}", fts_name))?;
+
+        let fulltext_columns = T::get_fulltext_columns();
+
+        if fulltext_columns.is_empty() {
+            return Ok(());
+        }
+
+        let primary_columns = T::primary_columns()
+            .into_iter()
+            .map(|column| column.name)
+            .filter(|name| !fulltext_columns.contains(name))
+            .collect::<Vec<_>>();
+
+        let definitions = primary_columns
+            .iter()
+            .map(|name| format!("{} UNINDEXED", name))
+            .chain(fulltext_columns.iter().map(ToString::to_string))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let columns = primary_columns
+            .iter()
+            .chain(fulltext_columns.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        let column_names = columns.join(", ");
+        let values = |row: &str| {
+            columns
+                .iter()
+                .map(|name| format!("{}.{}", row, name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let primary_condition = T::primary_columns()
+            .iter()
+            .map(|column| format!("{name} = old.{name}", name = column.name))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+
+        let insert = format!(
+            "INSERT INTO {fts} ({columns}) VALUES ({values});",
+            fts = fts_name,
+            columns = column_names,
+            values = values("new"),
+        );
+        let delete = format!(
+            "DELETE FROM {fts} WHERE {condition};",
+            fts = fts_name,
+            condition = primary_condition,
+        );
+
+        database.execute(format!(
+            "CREATE VIRTUAL TABLE {} USING fts5({})",
+            fts_name, definitions
+        ))?;
+
+        database.execute(format!(
+            "CREATE TRIGGER {fts}_insert AFTER INSERT ON {table} BEGIN {insert} END",
+            fts = fts_name,
+            table = table_name,
+            insert = insert,
+        ))?;
+        database.execute(format!(
+            "CREATE TRIGGER {fts}_delete AFTER DELETE ON {table} BEGIN {delete} END",
+            fts = fts_name,
+            table = table_name,
+            delete = delete,
+        ))?;
+        database.execute(format!(
+            "CREATE TRIGGER {fts}_update AFTER UPDATE ON {table} BEGIN {delete} {insert} END",
+            fts = fts_name,
+            table = table_name,
+            delete = delete,
+            insert = insert,
+        ))?;
+
+        database.execute(format!(
+            "INSERT INTO {fts} ({columns}) SELECT {columns} FROM {table}",
+            fts = fts_name,
+            columns = column_names,
+            table = table_name,
+        ))
+    }
+}
+
+/// Searches the full-text columns of a table and returns the primary key
+/// and the rank of every matching row, best matches first.
+///
+/// Every word of the text has to match the start of a word in one of the columns.
+/// Deleted rows of a table with `#[soft_delete]` are never found.
+/// The rank is the bm25 score of FTS5, where a smaller value is a better match.
+pub struct SearchQuery<T: Table> {
+    text: String,
+    filter: Option<Filter<T>>,
+    limit: Option<usize>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Table> SearchQuery<T> {
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        Self {
+            text: text.into(),
+            filter: None,
+            limit: None,
+            phantom: PhantomData,
+        }
+    }
+
+    // Builders
+
+    /// Only searches the rows matching the filter
+    pub fn filter(mut self, filter: Filter<T>) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    // Runners
+
+    pub fn get_query(&self) -> String {
+        let table_name = T::table_name();
+        let fts_name = fulltext_table_name(table_name);
+        let primary_names = T::primary_columns()
+            .into_iter()
+            .map(|column| column.name)
+            .collect::<Vec<_>>();
+
+        let conditions = [
+            self.filter.as_ref().map(ToString::to_string),
+            T::is_soft_delete().then(|| format!("{} IS NULL", DELETED_AT_COLUMN)),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|condition| format!("({})", condition))
+        .collect::<Vec<_>>();
+
+        // The conditions are applied in a subquery, so their column names refer to the table
+        let rows = if conditions.is_empty() {
+            table_name.to_owned()
+        } else {
+            format!(
+                "(SELECT {primary} FROM {table} WHERE {conditions}) AS {table}",
+                primary = primary_names.join(", "),
+                table = table_name,
+                conditions = conditions.join(" AND "),
+            )
+        };
+
+        let mut query = format!(
+            "SELECT {primary}, {fts}.rank FROM {fts} JOIN {rows} ON {join} WHERE {fts} MATCH ?",
+            primary = primary_names
+                .iter()
+                .map(|name| format!("{}.{}", table_name, name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            fts = fts_name,
+            rows = rows,
+            join = primary_names
+                .iter()
+                .map(|name| format!("{}.{name} = {}.{name}", table_name, fts_name, name = name))
+                .collect::<Vec<_>>()
+                .join(" AND "),
+        );
+
+        query += format!(" ORDER BY {}.rank", fts_name).as_str();
+
+        if let Some(limit) = self.limit {
+            query += format!(" LIMIT {}", limit).as_str();
+        }
+
+        query
+    }
+
+    /// Quotes every word, so the text can not contain FTS5 query syntax.
+    fn match_expression(&self) -> String {
+        self.text
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Reads the primary key and rank of each match, e.g. into `(Key, f64)`.
+    ///
+    /// The rank follows all columns of a composite primary key.
+    pub fn get_all<R: FromRow>(self, database: &Database) -> Result<Vec<R>> {
+        let expression = self.match_expression();
+
+        if expression.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut statement = database.prepare(self.get_query())?;
+
+        // The parameters of the filter come before the match expression in the query
+        let mut counter = 0;
+
+        if let Some(filter) = self.filter {
+            filter.bind_counted(&mut statement, &mut counter)?;
+        }
+
+        statement.bind((counter + 1, expression.as_str()))?;
+
+        let mut data = Vec::new();
+
+        while let State::Row = statement.next()? {
+            data.push(R::from_row(&statement.row())?);
+        }
+
+        Ok(data)
+    }
+}
@@ -0,0 +1,62 @@
+use std::marker::PhantomData;
+
+use crate::{query::migrate_table::read_count, Database, Error, Result, Table};
+
+/// Drops the given indices and creates all indices of the table that do not exist yet.
+pub struct IndexQuery<T: Table> {
+    dropped: Vec<String>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Table> IndexQuery<T> {
+    pub fn new(dropped: Vec<String>) -> Self {
+        Self {
+            dropped,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn run(self, database: &Database) -> Result<()> {
+        let table_name = T::table_name();
+
+        for name in self.dropped {
+            database.execute(format!("DROP INDEX IF EXISTS {}", name))?;
+        }
+
+        for index in T::get_indices() {
+            if index.unique {
+                let not_null = index
+                    .columns
+                    .iter()
+                    .map(|name| format!("{} IS NOT NULL", name))
+                    .collect::<Vec<_>>()
+                    .join(" AND ");
+
+                let q = format!(
+                    "SELECT COUNT(*) FROM (SELECT 1 FROM {} WHERE {} GROUP BY {} HAVING COUNT(*) > 1)",
+                    table_name,
+                    not_null,
+                    index.columns.join(", ")
+                );
+
+                if read_count(database, q)? > 0 {
+                    return Err(Error::Migration(format!(
+                        "columns ({}) of table '{}' contain duplicate values and can not be made unique",
+                        index.columns.join(", "),
+                        table_name
+                    )));
+                }
+            }
+
+            database.execute(format!(
+                "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
+                if index.unique { "UNIQUE " } else { "" },
+                index.name(table_name),
+                table_name,
+                index.columns.join(", ")
+            ))?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
 use std::marker::PhantomData;
 
-use crate::{table::Insertable, Database, Key, Table};
+use sqlite::Value;
+
+use crate::{
+    observer::ChangeKind,
+    table::{primary_names, Insertable, DELETED_AT_COLUMN},
+    Database, PrimaryKey, Result, Row, Table,
+};
 
 pub struct InsertQuery<I: Insertable<T>, T: Table> {
     data: I,
@@ -15,18 +21,31 @@ impl<I: Insertable<T>, T: Table> InsertQuery<I, T> {
         }
     }
 
-    pub fn run(self, database: &Database) -> sqlite::Result<Key> {
+    /// Returns the primary key of the inserted row.
+    ///
+    /// A row with the same primary key is updated, and restored if it was deleted from a table with `#[soft_delete]`.
+    pub fn run(self, database: &Database) -> Result<T::Primary> {
+        if database.needs_implicit_transaction::<T>(ChangeKind::Insert) {
+            return database.transaction(|transaction| self.run(transaction));
+        }
+
         let table_name = T::table_name();
-        let primary_name = T::primary_column().name;
+        let primary_names = primary_names::<T>();
 
         let column_names = I::get_column_names().join(", ");
         let placeholder_names = I::get_placeholder_names().join(", ");
 
-        let update_columns = I::get_column_names()
+        let mut update_columns = I::get_column_names()
             .iter()
             .map(|name| format!("{}=excluded.{}", name, name))
-            .collect::<Vec<_>>()
-            .join(", ");
+            .collect::<Vec<_>>();
+
+        // Inserting the key of a deleted row brings the row back instead of updating it invisibly
+        if T::is_soft_delete() {
+            update_columns.push(format!("{}=NULL", DELETED_AT_COLUMN));
+        }
+
+        let update_columns = update_columns.join(", ");
 
         let q = format!(
             "INSERT INTO {} ({}) VALUES ({})
@@ -38,20 +57,63 @@ impl<I: Insertable<T>, T: Table> InsertQuery<I, T> {
             // Values
             placeholder_names,
             // Conflict
-            primary_name,
+            primary_names,
             update_columns,
             // Return
-            primary_name
+            primary_names
         );
 
         let mut statement = database.prepare(q)?;
 
-        self.data.bind(&mut statement)?;
+        for (index, value) in self.data.into_values().into_iter().enumerate() {
+            statement.bind::<(_, Value)>((index + 1, value.into()))?;
+        }
 
         statement.next()?;
 
-        let id: String = statement.read(primary_name)?;
+        let id = T::Primary::read(&Row::new(&statement))?;
+
+        drop(statement);
+
+        database.record_change::<T>(ChangeKind::Insert, vec![id.clone()]);
+
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, Error, Key, Table};
+
+    #[derive(Table)]
+    #[soft_delete]
+    pub struct Note {
+        #[primary]
+        id: Key<Self>,
+        text: String,
+    }
+
+    #[test]
+    fn upsert_restores_a_soft_deleted_row() {
+        let mut database = Database::open_in_memory().unwrap();
+        database.register::<Note>().unwrap();
+
+        let id = database
+            .insert(Note {
+                id: Key::generate(),
+                text: "draft".to_owned(),
+            })
+            .unwrap();
+        database.delete::<Note>(id.clone()).unwrap();
+        assert!(matches!(database.get::<Note>(id.clone()), Err(Error::NotFound)));
+
+        database
+            .insert(Note {
+                id: id.clone(),
+                text: "final".to_owned(),
+            })
+            .unwrap();
 
-        Ok(Key::from(id))
+        assert_eq!(database.get::<Note>(id).unwrap().text, "final");
     }
 }
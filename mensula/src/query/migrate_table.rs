@@ -0,0 +1,380 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use sqlite::State;
+
+use crate::{meta::{ColumnDifference, Meta}, query::{CreateTableQuery, FulltextQuery, IndexQuery}, table::primary_names, Database, Error, Result, Table};
+
+pub struct MigrateTableQuery<T: Table> {
+    difference: HashMap<String, ColumnDifference>,
+    dropped_indices: Vec<String>,
+    primary_changed: bool,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Table> MigrateTableQuery<T> {
+    pub fn new(difference: HashMap<String, ColumnDifference>, dropped_indices: Vec<String>) -> Self {
+        Self {
+            difference,
+            dropped_indices,
+            primary_changed: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The primary key changed, so the table is always rebuilt.
+    ///
+    /// Fails if rows have the same value for the new primary key.
+    pub fn primary_changed(mut self) -> Self {
+        self.primary_changed = true;
+        self
+    }
+
+    /// Checks and changes the table and its entry in the meta table in the same transaction.
+    ///
+    /// Fails inside of a transaction, because foreign keys can only be turned off outside of one.
+    pub fn run(self, database: &Database) -> Result<()> {
+        if !database.is_autocommit() {
+            return Err(Error::Migration(format!(
+                "cannot migrate table '{}' inside of a transaction",
+                T::table_name()
+            )));
+        }
+
+        if !self.primary_changed && self.difference.values().all(ColumnDifference::is_addable) {
+            return database.transaction(|transaction| {
+                self.check(transaction)?;
+                self.add_columns(transaction)?;
+                IndexQuery::<T>::new(self.dropped_indices.clone()).run(transaction)?;
+                FulltextQuery::<T>::new().run(transaction)?;
+                Meta::store_table::<T>(transaction)
+            });
+        }
+
+        // Has no effect inside a transaction, so it needs to be changed before the rebuild starts
+        database.execute("PRAGMA foreign_keys = OFF")?;
+
+        let result = database.transaction(|transaction| {
+            self.check(transaction)?;
+            self.rebuild(transaction)?;
+            // Dropping the old table also dropped its indices and triggers
+            IndexQuery::<T>::new(Vec::new()).run(transaction)?;
+            FulltextQuery::<T>::new().run(transaction)?;
+            Meta::store_table::<T>(transaction)
+        });
+
+        database.execute("PRAGMA foreign_keys = ON")?;
+
+        result
+    }
+
+    fn is_new_column(&self, name: &str) -> bool {
+        self.difference
+            .get(name)
+            .is_some_and(|difference| difference.before.is_none())
+    }
+
+    /// Rejects every change that would lose data or could not be filled with a value.
+    fn check(&self, database: &Database) -> Result<()> {
+        let table_name = T::table_name();
+
+        if self.primary_changed {
+            self.check_primary(database)?;
+        }
+
+        for (name, difference) in &self.difference {
+            match (&difference.before, &difference.after) {
+                (None, Some(after))
+                    if !after.optional
+                        && after.default.is_none()
+                        && count_rows(database, table_name, None)? > 0 =>
+                {
+                    return Err(Error::Migration(format!(
+                        "cannot add required column '{}' to non-empty table '{}'",
+                        name, table_name
+                    )));
+                }
+                (Some(before), Some(after)) => {
+                    if before.optional && !after.optional {
+                        let condition = format!("{} IS NULL", name);
+                        if count_rows(database, table_name, Some(&condition))? > 0 {
+                            return Err(Error::Migration(format!(
+                                "column '{}' of table '{}' contains NULL values and can not be made required",
+                                name, table_name
+                            )));
+                        }
+                    }
+
+                    if before.data_type != after.data_type {
+                        let condition = format!(
+                            "{name} IS NOT NULL AND CAST(CAST({name} AS {after}) AS {before}) IS NOT {name}",
+                            name = name,
+                            before = before.data_type.as_ref(),
+                            after = after.data_type.as_ref(),
+                        );
+                        if count_rows(database, table_name, Some(&condition))? > 0 {
+                            return Err(Error::Migration(format!(
+                                "column '{}' of table '{}' contains values that can not be converted from {} to {}",
+                                name,
+                                table_name,
+                                before.data_type.as_ref(),
+                                after.data_type.as_ref()
+                            )));
+                        }
+                    }
+
+                    if let Some(check) = after.check.as_ref().filter(|check| before.check.as_ref() != Some(check)) {
+                        let condition = format!("NOT ({})", check);
+                        if count_rows(database, table_name, Some(&condition))? > 0 {
+                            return Err(Error::Migration(format!(
+                                "column '{}' of table '{}' contains values violating the check '{}'",
+                                name, table_name, check
+                            )));
+                        }
+                    }
+
+                    if !before.unique && after.unique {
+                        let q = format!(
+                            "SELECT COUNT({name}) - COUNT(DISTINCT {name}) FROM {table}",
+                            name = name,
+                            table = table_name
+                        );
+                        if read_count(database, q)? > 0 {
+                            return Err(Error::Migration(format!(
+                                "column '{}' of table '{}' contains duplicate values and can not be made unique",
+                                name, table_name
+                            )));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rejects a new primary column for existing rows, as it would not have a value to identify them,
+    /// and rows that would have the same value for the new primary key.
+    fn check_primary(&self, database: &Database) -> Result<()> {
+        let table_name = T::table_name();
+
+        let has_new_column = T::primary_columns()
+            .into_iter()
+            .any(|column| self.is_new_column(column.name));
+
+        if has_new_column && count_rows(database, table_name, None)? > 0 {
+            return Err(Error::Migration(format!(
+                "cannot add a new column to the primary key of non-empty table '{}'",
+                table_name
+            )));
+        }
+
+        let q = format!(
+            "SELECT COALESCE(SUM(count), 0) FROM (SELECT COUNT(*) AS count FROM {} GROUP BY {} HAVING count > 1)",
+            table_name,
+            primary_names::<T>()
+        );
+        let colliding = read_count(database, q)?;
+
+        if colliding > 0 {
+            return Err(Error::Migration(format!(
+                "{} rows of table '{}' have the same value for the new primary key ({})",
+                colliding,
+                table_name,
+                primary_names::<T>()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn add_columns(&self, database: &Database) -> Result<()> {
+        for column in T::get_columns() {
+            if self.is_new_column(column.name) {
+                database.execute(format!(
+                    "ALTER TABLE {} ADD COLUMN {}",
+                    T::table_name(),
+                    column
+                ))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rebuilds the table by copying it into a new table with the current schema,
+    /// as described in https://www.sqlite.org/lang_altertable.html#otheralter
+    fn rebuild(&self, database: &Database) -> Result<()> {
+        let table_name = T::table_name();
+        let new_table_name = format!("_mensula_new_{}", table_name);
+
+        let copy_columns = T::get_columns()
+            .into_iter()
+            .map(|column| column.name)
+            .filter(|name| !self.is_new_column(name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        database.execute(CreateTableQuery::<T>::new().get_query(&new_table_name))?;
+        database.execute(format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {}",
+            new_table_name, copy_columns, copy_columns, table_name
+        ))?;
+        database.execute(format!("DROP TABLE {}", table_name))?;
+        database.execute(format!(
+            "ALTER TABLE {} RENAME TO {}",
+            new_table_name, table_name
+        ))?;
+
+        let mut statement = database.prepare(format!("PRAGMA foreign_key_check({})", table_name))?;
+        if let State::Row = statement.next()? {
+            return Err(Error::Migration(format!(
+                "table '{}' violates foreign key constraints after migration",
+                table_name
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn count_rows(database: &Database, table_name: &str, condition: Option<&str>) -> Result<i64> {
+    let mut q = format!("SELECT COUNT(*) FROM {}", table_name);
+
+    if let Some(condition) = condition {
+        q += format!(" WHERE {}", condition).as_str();
+    }
+
+    read_count(database, q)
+}
+
+pub(super) fn read_count(database: &Database, q: String) -> Result<i64> {
+    let mut statement = database.prepare(q)?;
+    statement.next()?;
+    Ok(statement.read(0)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, Error, Key, Table};
+
+    #[derive(Table)]
+    #[table_name("Person")]
+    pub struct Person {
+        #[primary]
+        id: Key<Self>,
+        name: String,
+        age: i64,
+    }
+
+    #[derive(Table)]
+    #[table_name("Person")]
+    pub struct PersonWithOptionalAge {
+        #[primary]
+        id: Key<Self>,
+        name: String,
+        age: Option<i64>,
+    }
+
+    #[derive(Table)]
+    #[table_name("Person")]
+    pub struct PersonWithEmail {
+        #[primary]
+        id: Key<Self>,
+        name: String,
+        age: i64,
+        email: String,
+    }
+
+    #[derive(Table)]
+    #[table_name("Person")]
+    pub struct PersonByName {
+        #[primary]
+        name: String,
+        age: i64,
+    }
+
+    fn database(people: &[(&str, i64)]) -> Database {
+        let mut database = Database::open_in_memory().unwrap();
+        database.register::<Person>().unwrap();
+
+        for (name, age) in people {
+            database
+                .insert(Person {
+                    id: Key::generate(),
+                    name: name.to_string(),
+                    age: *age,
+                })
+                .unwrap();
+        }
+
+        database
+    }
+
+    #[test]
+    fn rebuild_keeps_the_rows() {
+        let mut database = database(&[("alice", 30), ("bob", 40)]);
+
+        database.register::<PersonWithOptionalAge>().unwrap();
+
+        let mut ages = database
+            .get_all::<PersonWithOptionalAge>()
+            .unwrap()
+            .into_iter()
+            .map(|person| (person.name, person.age))
+            .collect::<Vec<_>>();
+        ages.sort();
+
+        assert_eq!(
+            ages,
+            vec![("alice".to_owned(), Some(30)), ("bob".to_owned(), Some(40))]
+        );
+    }
+
+    #[test]
+    fn rebuild_changes_the_primary_key() {
+        let mut database = database(&[("alice", 30), ("bob", 40)]);
+
+        database.register::<PersonByName>().unwrap();
+
+        assert_eq!(database.get::<PersonByName>("bob".to_owned()).unwrap().age, 40);
+    }
+
+    #[test]
+    fn migration_aborts_when_adding_a_required_column_to_rows() {
+        let mut database = database(&[("alice", 30)]);
+
+        let result = database.register::<PersonWithEmail>();
+
+        assert!(matches!(result, Err(Error::Migration(_))));
+        assert_eq!(database.get_all::<Person>().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn migration_aborts_when_rows_collide_in_the_new_primary_key() {
+        let mut database = database(&[("alice", 30), ("alice", 31), ("bob", 40)]);
+
+        let result = database.register::<PersonByName>();
+
+        assert!(matches!(&result, Err(Error::Migration(message)) if message.starts_with("2 rows")));
+        assert_eq!(database.get_all::<Person>().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn migration_aborts_when_making_a_column_with_nulls_required() {
+        let mut database = Database::open_in_memory().unwrap();
+        database.register::<PersonWithOptionalAge>().unwrap();
+        database
+            .insert(PersonWithOptionalAge {
+                id: Key::generate(),
+                name: "alice".to_owned(),
+                age: None,
+            })
+            .unwrap();
+
+        let result = database.register::<Person>();
+
+        assert!(matches!(result, Err(Error::Migration(_))));
+        assert_eq!(database.get_all::<PersonWithOptionalAge>().unwrap().len(), 1);
+    }
+}
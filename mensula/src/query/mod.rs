@@ -1,10 +1,23 @@
+mod aggregate;
 mod create_table;
 mod insert;
 mod select;
+mod update;
 mod delete;
+mod migrate_table;
+mod index;
+mod fulltext;
 
+pub use aggregate::AggregateQuery;
 pub use create_table::CreateTableQuery;
 pub use insert::InsertQuery;
 pub use select::SelectQuery;
 pub use select::Ordering;
-pub use delete::DeleteQuery;
\ No newline at end of file
+pub use select::Cursor;
+pub use select::Page;
+pub use update::UpdateQuery;
+pub use delete::DeleteQuery;
+pub use migrate_table::MigrateTableQuery;
+pub use index::IndexQuery;
+pub use fulltext::FulltextQuery;
+pub use fulltext::SearchQuery;
\ No newline at end of file
@@ -1,9 +1,18 @@
-use std::{fmt::Display, marker::PhantomData};
+use std::{collections::HashMap, fmt::Display, marker::PhantomData};
 
 use mensula_key::Key;
-use sqlite::{State, Statement};
+use serde::{Deserialize, Serialize};
+use sqlite::{State, Statement, Value};
 
-use crate::{filter::Filter, table::Readable, Column, Database, Table, Link};
+use crate::{
+  filter::{Filter, FilterValue},
+  statement_cache::CachedStatement,
+  table::{primary_names, Readable, DELETED_AT_COLUMN},
+  Column, Database, Link, PrimaryKey, Result, Table,
+};
+
+const CURSOR_NAME: &str = "_mensula_cursor";
+const LINK_PARENT_NAME: &str = "_mensula_link_parent";
 
 pub enum Ordering {
   Ascending,
@@ -23,9 +32,66 @@ impl Display for Ordering {
   }
 }
 
+/// The position of the last row of a [`Page`].
+///
+/// Contains the values of the ordering column and all primary columns of that row,
+/// so rows with the same value in the ordering column are not skipped.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Cursor {
+  values: Vec<FilterValue>,
+}
+
+pub struct Page<R> {
+  pub rows: Vec<R>,
+  /// `None` if this is the last page
+  pub next: Option<Cursor>,
+}
+
+enum After {
+  Column(&'static str, FilterValue),
+  Cursor(Cursor),
+}
+
+/// Which rows of a table with `#[soft_delete]` are selected
+pub(super) enum Deleted {
+  Exclude,
+  Include,
+  Only,
+}
+
+impl Deleted {
+  /// The condition selecting the rows of `T`, `None` if all rows are selected
+  pub(super) fn condition<T: Table>(&self) -> Option<String> {
+    if !T::is_soft_delete() {
+      return None;
+    }
+
+    match self {
+      Deleted::Exclude => Some(format!("{} IS NULL", DELETED_AT_COLUMN)),
+      Deleted::Include => None,
+      Deleted::Only => Some(format!("{} IS NOT NULL", DELETED_AT_COLUMN)),
+    }
+  }
+}
+
+/// Joins the selected table with a link table to load the rows linked to many parents at once.
+struct LinkJoin {
+  link_table_name: &'static str,
+  own_link_name: &'static str,
+  parent_link_name: &'static str,
+  keys: Vec<Key>,
+}
+
 pub struct SelectQuery<T: Table> {
   filter: Option<Filter<T>>,
   ordering: Option<(&'static str, Ordering)>,
+  after: Option<After>,
+  limit: Option<usize>,
+  offset: Option<usize>,
+  link: Option<LinkJoin>,
+  deleted: Deleted,
+  /// Select the primary columns before the columns of the row
+  with_keys: bool,
   phantom: PhantomData<T>,
 }
 
@@ -34,11 +100,17 @@ impl<T: Table> SelectQuery<T> {
     Self {
       filter: None,
       ordering: None,
+      after: None,
+      limit: None,
+      offset: None,
+      link: None,
+      deleted: Deleted::Exclude,
+      with_keys: false,
       phantom: PhantomData,
     }
   }
 
-  pub fn link<U: Table, L: Link<T> + Link<U> + Table>(key: Key) -> Self {
+  pub fn link<U: Table, L: Link<T> + Link<U> + Table>(key: Key<U>) -> Self {
     SelectQuery::new().filter(T::primary_column().link::<L, U>(key))
   }
 
@@ -54,74 +126,510 @@ impl<T: Table> SelectQuery<T> {
     self
   }
 
+  pub fn limit(mut self, limit: usize) -> Self {
+    self.limit = Some(limit);
+    self
+  }
+
+  pub fn offset(mut self, offset: usize) -> Self {
+    self.offset = Some(offset);
+    self
+  }
+
+  /// Only select rows that come after `value` in `column`.
+  ///
+  /// If the query is ordered descending by `column`, this selects the rows with a smaller value.
+  /// Without an ordering, the rows are ordered ascending by `column`.
+  /// `column` should be unique, use [`Self::get_page`] to page through non unique columns.
+  pub fn after<V: Into<FilterValue>>(mut self, column: Column<T>, value: V) -> Self {
+    self.after = Some(After::Column(column.name, value.into()));
+    self
+  }
+
+  /// Continue after the last row of a previous [`Page`].
+  ///
+  /// The query needs to have the same ordering as the one that created the cursor.
+  pub fn after_cursor(mut self, cursor: Cursor) -> Self {
+    self.after = Some(After::Cursor(cursor));
+    self
+  }
+
+  /// Also select deleted rows of a table with `#[soft_delete]`, which are excluded by default.
+  pub fn with_deleted(mut self) -> Self {
+    self.deleted = Deleted::Include;
+    self
+  }
+
+  /// Only select deleted rows of a table with `#[soft_delete]`.
+  pub fn only_deleted(mut self) -> Self {
+    self.deleted = Deleted::Only;
+    self
+  }
+
   // Runners
 
   pub fn get_query<R>(&self) -> String
   where
     T: Readable<R>,
   {
-    let column_names = if let Some(cols) = T::get_column_names() {
+    self.build_query(false)
+  }
+
+  /// The ordering column, which defaults to the column of [`Self::after`] and then to the primary column
+  fn order_column(&self) -> &'static str {
+    match (&self.ordering, &self.after) {
+      (Some((name, _)), _) => name,
+      (None, Some(After::Column(name, _))) => name,
+      (None, _) => T::primary_column().name,
+    }
+  }
+
+  fn order_direction(&self) -> &Ordering {
+    match &self.ordering {
+      Some((_, ordering)) => ordering,
+      None => &Ordering::Ascending,
+    }
+  }
+
+  /// The columns that determine the order of the rows,
+  /// which are the ordering column followed by the remaining primary columns.
+  fn cursor_columns(&self) -> Vec<&'static str> {
+    let order_name = self.order_column();
+
+    let mut columns = vec![order_name];
+    columns.extend(
+      T::primary_columns()
+        .into_iter()
+        .map(|column| column.name)
+        .filter(|name| *name != order_name),
+    );
+
+    columns
+  }
+
+  /// The condition selecting the rows after [`Self::after`] and the values to bind for it
+  fn after_condition(&self) -> Option<(String, Vec<FilterValue>)> {
+    match self.after.as_ref()? {
+      After::Column(name, value) if *name != self.order_column() => {
+        Some((format!("{} > ?", name), vec![value.clone()]))
+      }
+      After::Column(name, value) => Some(keyset_condition(
+        &[name],
+        vec![value.clone()],
+        self.order_direction(),
+      )),
+      After::Cursor(cursor) => Some(keyset_condition(
+        &self.cursor_columns(),
+        cursor.values.clone(),
+        self.order_direction(),
+      )),
+    }
+  }
+
+  fn build_query<R>(&self, with_cursor: bool) -> String
+  where
+    T: Readable<R>,
+  {
+    let mut column_names = if let Some(cols) = T::get_column_names() {
       cols.join(", ")
     } else {
       "*".to_string()
     };
 
-    let mut query = format!("SELECT {} FROM {}", column_names, T::table_name());
+    if self.with_keys {
+      column_names = format!("{}, {}", primary_names::<T>(), column_names);
+    }
 
-    if let Some(filter) = &self.filter {
-      query += format!(" WHERE {}", filter).as_str();
+    if with_cursor {
+      for (index, name) in self.cursor_columns().into_iter().enumerate() {
+        column_names += format!(", {} AS {}_{}", name, CURSOR_NAME, index).as_str();
+      }
     }
 
-    if let Some((order_by, ordering)) = &self.ordering {
-      query += format!(" ORDER BY {} {}", order_by, ordering).as_str();
+    let source = match &self.link {
+      Some(link) => {
+        column_names += format!(", {}", LINK_PARENT_NAME).as_str();
+
+        // The joined rows are wrapped in a subquery named like the table,
+        // so filters and orderings can keep using unqualified column names.
+        format!(
+          "(SELECT {table}.*, {link}.{parent} AS {parent_name} FROM {table} JOIN {link} ON {table}.{primary} = {link}.{own}) AS {table}",
+          table = T::table_name(),
+          link = link.link_table_name,
+          parent = link.parent_link_name,
+          parent_name = LINK_PARENT_NAME,
+          primary = T::primary_column().name,
+          own = link.own_link_name,
+        )
+      }
+      None => T::table_name().to_string(),
+    };
+
+    let mut query = format!("SELECT {} FROM {}", column_names, source);
+
+    let link_condition = self.link.as_ref().map(|link| {
+      format!(
+        "{} IN ({})",
+        LINK_PARENT_NAME,
+        vec!["?"; link.keys.len()].join(", ")
+      )
+    });
+
+    let conditions = [
+      self.filter.as_ref().map(ToString::to_string),
+      self.after_condition().map(|(condition, _)| condition),
+      self.deleted.condition::<T>(),
+      link_condition,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|condition| format!("({})", condition))
+    .collect::<Vec<_>>();
+
+    if !conditions.is_empty() {
+      query += format!(" WHERE {}", conditions.join(" AND ")).as_str();
+    }
+
+    // Rows can only come after others if they are ordered
+    if with_cursor || self.ordering.is_some() || self.after.is_some() {
+      let ordering = self.order_direction();
+
+      // The primary columns keep the order stable for rows with the same value
+      let order_by = self
+        .cursor_columns()
+        .into_iter()
+        .map(|name| format!("{} {}", name, ordering))
+        .collect::<Vec<_>>();
+
+      query += format!(" ORDER BY {}", order_by.join(", ")).as_str();
+    }
+
+    if let Some(limit) = self.limit {
+      query += format!(" LIMIT {}", limit).as_str();
+    } else if self.offset.is_some() {
+      query += " LIMIT -1";
+    }
+
+    if let Some(offset) = self.offset {
+      query += format!(" OFFSET {}", offset).as_str();
     }
 
     query
   }
 
-  fn run<R>(self, database: &Database) -> sqlite::Result<Statement>
+  fn run<R>(self, database: &Database, with_cursor: bool) -> Result<CachedStatement<'_>>
   where
     T: Readable<R>,
   {
-    let q = self.get_query();
+    let q = self.build_query(with_cursor);
+
+    let values = self
+      .after_condition()
+      .map(|(_, values)| values)
+      .unwrap_or_default();
 
     let mut statement = database.prepare(q)?;
 
+    let mut counter = 0;
+
     if let Some(filter) = self.filter {
-      filter.bind(&mut statement)?;
+      filter.bind_counted(&mut statement, &mut counter)?;
+    }
+
+    let link_keys = self.link.map(|link| link.keys).unwrap_or_default();
+
+    for value in values
+      .into_iter()
+      .chain(link_keys.into_iter().map(FilterValue::from))
+    {
+      counter += 1;
+      statement.bind::<(_, Value)>((counter, value.into()))?;
     }
 
     Ok(statement)
   }
 
-  pub fn get_all<R>(self, database: &Database) -> Option<Vec<R>>
+  pub fn get_all<R>(self, database: &Database) -> Result<Vec<R>>
   where
     T: Readable<R>,
   {
     let mut data = Vec::new();
 
-    let mut statement = self.run(database).ok()?;
+    let mut statement = self.run(database, false)?;
 
-    while let State::Row = statement.next().ok()? {
-      data.push(T::read(&statement)?);
+    while let State::Row = statement.next()? {
+      data.push(T::read(&statement.row())?);
     }
 
-    Some(data)
+    Ok(data)
   }
 
-  pub fn get_first<R>(self, database: &Database) -> Option<R>
+  pub fn get_first<R>(self, database: &Database) -> Result<Option<R>>
   where
     T: Readable<R>,
   {
-    let mut statement = self.run(database).ok()?;
-    if let State::Row = statement.next().ok()? {
-      T::read(&statement)
+    let mut statement = self.run(database, false)?;
+    if let State::Row = statement.next()? {
+      T::read(&statement.row()).map(Some)
     } else {
-      None
+      Ok(None)
     }
   }
+
+  /// Get the primary key of each row together with the row.
+  pub(crate) fn get_all_with_keys<R>(mut self, database: &Database) -> Result<Vec<(T::Primary, R)>>
+  where
+    T: Readable<R>,
+  {
+    self.with_keys = true;
+
+    let mut data = Vec::new();
+
+    let mut statement = self.run(database, false)?;
+
+    while let State::Row = statement.next()? {
+      let row = statement.row();
+      data.push((T::Primary::read(&row)?, T::read(&row)?));
+    }
+
+    Ok(data)
+  }
+
+  /// Get the rows linked to each of the `keys` of `U` via the link table `L`, using a single query.
+  ///
+  /// Every key is contained in the result, even if no rows are linked to it.
+  /// The filter and ordering apply to the linked rows, a limit or offset to all rows combined.
+  pub fn get_all_linked<U: Table, L: Link<T> + Link<U> + Table, R>(
+    mut self,
+    database: &Database,
+    keys: &[Key<U>],
+  ) -> Result<HashMap<Key<U>, Vec<R>>>
+  where
+    T: Readable<R>,
+  {
+    let mut data: HashMap<Key<U>, Vec<R>> = keys.iter().map(|key| (key.clone(), Vec::new())).collect();
+
+    if keys.is_empty() {
+      return Ok(data);
+    }
+
+    self.link = Some(LinkJoin {
+      link_table_name: L::table_name(),
+      own_link_name: <L as Link<T>>::link_name(),
+      parent_link_name: <L as Link<U>>::link_name(),
+      keys: keys.iter().cloned().map(Key::erase).collect(),
+    });
+
+    let mut statement = self.run(database, false)?;
+
+    while let State::Row = statement.next()? {
+      let parent = statement.row().read_named(LINK_PARENT_NAME)?;
+
+      data.entry(parent).or_default().push(T::read(&statement.row())?);
+    }
+
+    Ok(data)
+  }
+
+  /// Get at most `size` rows and a cursor to the next page.
+  ///
+  /// Pass the cursor to [`Self::after_cursor`] of an otherwise identical query to get the next page.
+  /// If no ordering is set, the rows are ordered by the primary column.
+  pub fn get_page<R>(mut self, database: &Database, size: usize) -> Result<Page<R>>
+  where
+    T: Readable<R>,
+  {
+    self.limit = Some(size);
+    let cursor_size = self.cursor_columns().len();
+
+    let mut rows = Vec::new();
+    let mut last = None;
+
+    let mut statement = self.run(database, true)?;
+
+    while let State::Row = statement.next()? {
+      rows.push(T::read(&statement.row())?);
+      last = Some(read_cursor(&statement, cursor_size)?);
+    }
+
+    let next = if rows.len() == size { last } else { None };
+
+    Ok(Page { rows, next })
+  }
+}
+
+/// Selects the rows after `values` of `columns`, which are the ordering column followed by primary columns.
+///
+/// SQLite sorts `NULL` first in ascending and last in descending order, and a comparison with `NULL`
+/// is never true, so the rows with `NULL` in the ordering column are selected explicitly.
+/// Primary columns are never `NULL`, so they are compared as a row value.
+fn keyset_condition(
+  columns: &[&str],
+  mut values: Vec<FilterValue>,
+  ordering: &Ordering,
+) -> (String, Vec<FilterValue>) {
+  let comparison = match ordering {
+    Ordering::Ascending => ">",
+    Ordering::Descending => "<",
+  };
+  let compare = |columns: &[&str]| match columns {
+    [name] => format!("{} {} ?", name, comparison),
+    columns => format!(
+      "({}) {} ({})",
+      columns.join(", "),
+      comparison,
+      vec!["?"; columns.len()].join(", ")
+    ),
+  };
+
+  let order_name = columns[0];
+  let rest = &columns[1..];
+
+  let condition = match (values.first(), ordering) {
+    (Some(FilterValue::Null), ordering) => {
+      // The `NULL` is not bound, as it is compared with `IS NULL`
+      values.remove(0);
+
+      match (rest.is_empty(), ordering) {
+        (true, Ordering::Ascending) => format!("{} IS NOT NULL", order_name),
+        (true, Ordering::Descending) => "FALSE".to_owned(),
+        (false, Ordering::Ascending) => format!(
+          "({name} IS NULL AND {rest}) OR {name} IS NOT NULL",
+          name = order_name,
+          rest = compare(rest)
+        ),
+        (false, Ordering::Descending) => {
+          format!("{} IS NULL AND {}", order_name, compare(rest))
+        }
+      }
+    }
+    (_, Ordering::Ascending) => compare(columns),
+    (_, Ordering::Descending) => format!("{} OR {} IS NULL", compare(columns), order_name),
+  };
+
+  (condition, values)
+}
+
+fn read_cursor(statement: &Statement, size: usize) -> Result<Cursor> {
+  let values = (0..size)
+    .map(|index| {
+      let value = statement.read::<Value, _>(format!("{}_{}", CURSOR_NAME, index).as_str())?;
+
+      Ok(value.into())
+    })
+    .collect::<Result<_>>()?;
+
+  Ok(Cursor { values })
 }
 
-// impl<T: Table + Readable<R>, R> SelectQuery<T> {
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Table)]
+  pub struct Item {
+    #[primary]
+    id: i64,
+    rank: Option<i64>,
+  }
+
+  /// A database with an item for each rank, numbered from 1
+  fn database(ranks: &[Option<i64>]) -> Database {
+    let mut database = Database::open_in_memory().unwrap();
+    database.register::<Item>().unwrap();
+
+    for (index, rank) in ranks.iter().enumerate() {
+      database
+        .insert(Item {
+          id: index as i64 + 1,
+          rank: *rank,
+        })
+        .unwrap();
+    }
+
+    database
+  }
+
+  /// The ids of every page of the queries built by `query`, following the cursors until the last page
+  fn pages(database: &Database, size: usize, query: impl Fn() -> SelectQuery<Item>) -> Vec<Vec<i64>> {
+    let mut pages = Vec::new();
+    let mut cursor = None;
+
+    loop {
+      let page = match cursor.take() {
+        Some(cursor) => query().after_cursor(cursor),
+        None => query(),
+      }
+      .get_page::<Item>(database, size)
+      .unwrap();
+
+      pages.push(page.rows.iter().map(|item| item.id).collect());
+
+      match page.next {
+        Some(next) => cursor = Some(next),
+        None => return pages,
+      }
+    }
+  }
 
-// }
+  #[test]
+  fn get_page_of_an_empty_table_is_the_last_page() {
+    let database = database(&[]);
+
+    assert_eq!(pages(&database, 2, SelectQuery::new), vec![Vec::<i64>::new()]);
+  }
+
+  #[test]
+  fn get_page_ends_with_a_partial_page() {
+    let database = database(&[None; 5]);
+
+    assert_eq!(pages(&database, 2, SelectQuery::new), vec![vec![1, 2], vec![3, 4], vec![5]]);
+  }
+
+  #[test]
+  fn get_page_ends_with_an_empty_page_if_the_rows_fill_the_pages() {
+    let database = database(&[None; 4]);
+
+    assert_eq!(pages(&database, 2, SelectQuery::new), vec![vec![1, 2], vec![3, 4], vec![]]);
+  }
+
+  #[test]
+  fn get_page_splits_rows_with_the_same_value_in_the_ordering_column() {
+    let database = database(&[Some(1), Some(1), Some(1), Some(0)]);
+
+    assert_eq!(
+      pages(&database, 2, || SelectQuery::new().order_by(Item::rank(), Ordering::Ascending)),
+      vec![vec![4, 1], vec![2, 3], vec![]]
+    );
+  }
+
+  #[test]
+  fn get_page_includes_null_values_of_the_ordering_column() {
+    let database = database(&[None, Some(5), None, Some(5), None, Some(1)]);
+
+    assert_eq!(
+      pages(&database, 2, || SelectQuery::new().order_by(Item::rank(), Ordering::Ascending)),
+      vec![vec![1, 3], vec![5, 6], vec![2, 4], vec![]]
+    );
+    assert_eq!(
+      pages(&database, 4, || SelectQuery::new().order_by(Item::rank(), Ordering::Descending)),
+      vec![vec![4, 2, 6, 5], vec![3, 1]]
+    );
+  }
+
+  #[test]
+  fn after_orders_by_its_column_without_an_ordering() {
+    let database = database(&[None; 5]);
+
+    let ids = SelectQuery::<Item>::new()
+      .after(Item::id(), 2)
+      .limit(2)
+      .get_all::<Item>(&database)
+      .unwrap()
+      .into_iter()
+      .map(|item| item.id)
+      .collect::<Vec<_>>();
+
+    assert_eq!(ids, vec![3, 4]);
+  }
+}
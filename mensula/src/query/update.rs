@@ -0,0 +1,116 @@
+use std::marker::PhantomData;
+
+use sqlite::{State, Value};
+
+use crate::{
+    filter::Filter, observer::ChangeKind, table::primary_names, Column, Database, FilterValue,
+    PrimaryKey, Result, Table,
+};
+
+/// Sets single columns of all rows matching a filter, leaving the other columns untouched.
+pub struct UpdateQuery<T: Table> {
+    assignments: Vec<(&'static str, FilterValue)>,
+    filter: Option<Filter<T>>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Table> Default for UpdateQuery<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Table> UpdateQuery<T> {
+    pub fn new() -> Self {
+        Self {
+            assignments: Vec::new(),
+            filter: None,
+            phantom: PhantomData,
+        }
+    }
+
+    // Builders
+
+    pub fn set<V: Into<FilterValue>>(mut self, column: Column<T>, value: V) -> Self {
+        self.assignments.push((column.name, value.into()));
+        self
+    }
+
+    /// Without a filter every row of the table is updated.
+    pub fn filter(mut self, filter: Filter<T>) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    // Runners
+
+    pub fn get_query(&self) -> String {
+        let assignments = self
+            .assignments
+            .iter()
+            .map(|(name, _)| format!("{} = ?", name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut query = format!("UPDATE {} SET {}", T::table_name(), assignments);
+
+        if let Some(filter) = &self.filter {
+            query += format!(" WHERE {}", filter).as_str();
+        }
+
+        query
+    }
+
+    /// Returns the number of updated rows.
+    pub fn run(self, database: &Database) -> Result<usize> {
+        if self.assignments.is_empty() {
+            return Ok(0);
+        }
+
+        if database.needs_implicit_transaction::<T>(ChangeKind::Update) {
+            return database.transaction(|transaction| self.run(transaction));
+        }
+
+        // The keys of the updated rows are only needed by observers
+        let is_observed = database.is_observed::<T>(ChangeKind::Update);
+
+        let mut q = self.get_query();
+
+        if is_observed {
+            q += format!(" RETURNING {}", primary_names::<T>()).as_str();
+        }
+
+        let mut statement = database.prepare(q)?;
+
+        let mut counter = 0;
+
+        for (_, value) in self.assignments {
+            counter += 1;
+            statement.bind::<(_, Value)>((counter, value.into()))?;
+        }
+
+        if let Some(filter) = self.filter {
+            filter.bind_counted(&mut statement, &mut counter)?;
+        }
+
+        if !is_observed {
+            statement.next()?;
+
+            return Ok(database.change_count());
+        }
+
+        let mut keys = Vec::new();
+
+        while let State::Row = statement.next()? {
+            keys.push(T::Primary::read(&statement.row())?);
+        }
+
+        drop(statement);
+
+        let count = keys.len();
+
+        database.record_change::<T>(ChangeKind::Update, keys);
+
+        Ok(count)
+    }
+}
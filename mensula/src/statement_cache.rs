@@ -0,0 +1,236 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, Instant};
+
+use sqlite::{State, Statement};
+use sqlite3_sys as ffi;
+
+use crate::logger::QueryInfo;
+use crate::{Database, Row};
+
+/// The maximum number of statements kept by a single connection,
+/// the least recently used one is dropped to make room for a new one
+const CAPACITY: usize = 128;
+
+/// Prepared statements of a connection, keyed by their SQL.
+///
+/// The statements are stored with a `'static` lifetime instead of borrowing the connection,
+/// because the cache is stored next to the connection in the [`crate::Database`].
+/// A `Statement` only borrows its connection so it is finalized before the connection is closed,
+/// which still holds:
+///
+/// - the cache is declared before the connection in [`crate::Database`], so it is dropped first,
+///   and the connection is never replaced while the database exists,
+/// - a statement is only handed out as a [`CachedStatement`], which borrows the database
+///   and puts the statement back into the cache when dropped.
+pub(crate) struct StatementCache {
+    statements: RefCell<HashMap<String, CacheEntry>>,
+    /// Counts up every time a statement is put back, to find the least recently used one
+    clock: Cell<u64>,
+    hits: Cell<usize>,
+    misses: Cell<usize>,
+}
+
+struct CacheEntry {
+    statement: Statement<'static>,
+    last_used: u64,
+}
+
+// A `Statement` is not `Send`, because it holds a raw pointer to its connection
+// and shares the names of its columns in an `Rc`.
+// Sending the cache is sound nonetheless:
+// - the statements are sent together with the connection they point to, as both are part of the
+//   same `Database`, and SQLite allows using a connection on another thread as long as it is
+//   only used by one thread at a time, which `Database` not being `Sync` ensures,
+// - the `Rc` is only ever cloned by `Statement::column_mapping` and cursors, which mensula does not use,
+//   and a statement is never handed to code outside of mensula: rows are read through a [`Row`],
+//   which only reads values, and values are bound by mensula itself,
+//   so every `Rc` is owned by its statement alone.
+unsafe impl Send for StatementCache {}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StatementCacheStats {
+    /// How often a cached statement was reused
+    pub hits: usize,
+    /// How often a statement had to be prepared
+    pub misses: usize,
+    /// The number of statements currently in the cache
+    pub cached: usize,
+}
+
+impl StatementCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            statements: RefCell::new(HashMap::new()),
+            clock: Cell::new(0),
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        }
+    }
+
+    /// Removes the statement from the cache, so it can not be used twice at the same time.
+    pub(crate) fn take(&self, sql: &str) -> Option<Statement<'static>> {
+        let statement = self
+            .statements
+            .borrow_mut()
+            .remove(sql)
+            .map(|entry| entry.statement);
+
+        match statement {
+            Some(_) => self.hits.set(self.hits.get() + 1),
+            None => self.misses.set(self.misses.get() + 1),
+        }
+
+        statement
+    }
+
+    pub(crate) fn put(&self, sql: String, mut statement: Statement<'static>) {
+        // The error of the last step is returned again, the statement can be reused anyway
+        let _ = statement.reset();
+
+        let mut statements = self.statements.borrow_mut();
+
+        // The same query was prepared again while the cached statement was in use
+        if statements.contains_key(&sql) {
+            return;
+        }
+
+        if statements.len() >= CAPACITY {
+            let least_recently_used = statements
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(sql, _)| sql.clone());
+
+            if let Some(sql) = least_recently_used {
+                statements.remove(&sql);
+            }
+        }
+
+        let last_used = self.clock.get();
+        self.clock.set(last_used + 1);
+
+        statements.insert(sql, CacheEntry { statement, last_used });
+    }
+
+    pub(crate) fn clear(&self) {
+        self.statements.borrow_mut().clear();
+    }
+
+    pub(crate) fn stats(&self) -> StatementCacheStats {
+        StatementCacheStats {
+            hits: self.hits.get(),
+            misses: self.misses.get(),
+            cached: self.statements.borrow().len(),
+        }
+    }
+}
+
+/// A statement that is reset and returned to the cache when dropped.
+///
+/// Steps through the statement with [`CachedStatement::next`] are timed
+/// and passed to the logger of the database once the statement is dropped.
+pub(crate) struct CachedStatement<'a> {
+    sql: String,
+    statement: Option<Statement<'static>>,
+    database: &'a Database,
+    execution: Option<Execution>,
+}
+
+/// What happened while stepping through a statement
+struct Execution {
+    duration: Duration,
+    rows: usize,
+    changes: usize,
+}
+
+impl<'a> CachedStatement<'a> {
+    pub(crate) fn new(sql: String, statement: Statement<'static>, database: &'a Database) -> Self {
+        Self {
+            sql,
+            statement: Some(statement),
+            database,
+            execution: None,
+        }
+    }
+
+    /// The current row, for reading it without handing out the statement
+    pub(crate) fn row(&self) -> Row<'_> {
+        Row::new(self)
+    }
+
+    /// Shadows [`Statement::next`] to measure the execution of the statement.
+    pub(crate) fn next(&mut self) -> sqlite::Result<State> {
+        let start = Instant::now();
+        let state = self.deref_mut().next();
+        let duration = start.elapsed();
+
+        let changes = match state {
+            Ok(State::Done) if self.column_count() == 0 => self.database.change_count(),
+            _ => 0,
+        };
+
+        let execution = self.execution.get_or_insert(Execution {
+            duration: Duration::ZERO,
+            rows: 0,
+            changes: 0,
+        });
+
+        execution.duration += duration;
+        execution.changes += changes;
+
+        if let Ok(State::Row) = state {
+            execution.rows += 1;
+        }
+
+        state
+    }
+
+    fn log(&self, statement: &Statement<'static>) {
+        let execution = match &self.execution {
+            Some(execution) => execution,
+            None => return,
+        };
+
+        // Safe because the statement is still alive
+        let parameters = unsafe { ffi::sqlite3_bind_parameter_count(statement.as_raw()) };
+
+        let rows = if statement.column_count() > 0 {
+            execution.rows
+        } else {
+            execution.changes
+        };
+
+        self.database.log_query(&QueryInfo {
+            sql: &self.sql,
+            parameters: parameters.max(0) as usize,
+            duration: execution.duration,
+            rows,
+        });
+    }
+}
+
+impl Deref for CachedStatement<'_> {
+    type Target = Statement<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        self.statement.as_ref().expect("statement already returned")
+    }
+}
+
+impl DerefMut for CachedStatement<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.statement.as_mut().expect("statement already returned")
+    }
+}
+
+impl Drop for CachedStatement<'_> {
+    fn drop(&mut self) {
+        if let Some(statement) = self.statement.take() {
+            self.log(&statement);
+            self.database
+                .statements()
+                .put(std::mem::take(&mut self.sql), statement);
+        }
+    }
+}
@@ -2,6 +2,11 @@ use std::{fmt::Display, marker::PhantomData};
 
 use crate::{filter::FilterValue, DataType, Filter, Link, Modifier, Table, Key};
 
+use super::DataTypeKind;
+
+/// The column added to tables with `#[soft_delete]`, which is set when a row is deleted
+pub(crate) const DELETED_AT_COLUMN: &str = "deleted_at";
+
 pub struct Column<T: Table> {
     pub name: &'static str,
     pub data_type: DataType,
@@ -19,15 +24,61 @@ impl<T: Table> Column<T> {
         }
     }
 
+    /// The `deleted_at` column of a table with `#[soft_delete]`,
+    /// which contains the time the row was deleted or `NULL`.
+    pub fn deleted_at() -> Self {
+        Self::new(
+            DELETED_AT_COLUMN,
+            DataType::from(DataTypeKind::Text).optional(),
+            Modifier::new(false, false, None),
+        )
+    }
+
     pub fn eq<V: Into<FilterValue>>(&self, value: V) -> Filter<T> {
         Filter::Eq(self.name, value.into())
     }
 
+    pub fn ne<V: Into<FilterValue>>(&self, value: V) -> Filter<T> {
+        Filter::Ne(self.name, value.into())
+    }
+
+    pub fn lt<V: Into<FilterValue>>(&self, value: V) -> Filter<T> {
+        Filter::Lt(self.name, value.into())
+    }
+
+    pub fn le<V: Into<FilterValue>>(&self, value: V) -> Filter<T> {
+        Filter::Le(self.name, value.into())
+    }
+
+    pub fn gt<V: Into<FilterValue>>(&self, value: V) -> Filter<T> {
+        Filter::Gt(self.name, value.into())
+    }
+
+    pub fn ge<V: Into<FilterValue>>(&self, value: V) -> Filter<T> {
+        Filter::Ge(self.name, value.into())
+    }
+
+    pub fn between<V: Into<FilterValue>>(&self, low: V, high: V) -> Filter<T> {
+        Filter::Between(self.name, low.into(), high.into())
+    }
+
+    pub fn is_null(&self) -> Filter<T> {
+        Filter::IsNull(self.name)
+    }
+
+    pub fn is_not_null(&self) -> Filter<T> {
+        Filter::IsNotNull(self.name)
+    }
+
+    pub fn in_values<V: Into<FilterValue>>(&self, values: impl IntoIterator<Item = V>) -> Filter<T> {
+        Filter::InValues(self.name, values.into_iter().map(Into::into).collect())
+    }
+
     pub fn like(&self, value: String) -> Filter<T> {
         Filter::Like(self.name, value.into())
     }
 
-    pub fn link<L: Table + Link<T> + Link<U>, U: Table>(&self, value: Key) -> Filter<T> {
+    pub fn link<L: Table + Link<T> + Link<U>, U: Table>(&self, value: Key<U>) -> Filter<T> {
         Filter::In {
             own_column_name: T::primary_column().name,
             other_column_name: <L as Link<T>>::link_name(),
@@ -38,6 +89,26 @@ impl<T: Table> Column<T> {
             )),
         }
     }
+
+    /// Matches the rows that are linked to any row of `U` via the link table `L`.
+    pub fn linked<L: Table + Link<T> + Link<U>, U: Table>(&self) -> Filter<T> {
+        Filter::In {
+            own_column_name: T::primary_column().name,
+            other_column_name: <L as Link<T>>::link_name(),
+            other_table_name: L::table_name(),
+            filter: Box::new(Filter::IsNotNull(<L as Link<U>>::link_name())),
+        }
+    }
+
+    /// Matches the rows whose value of this column is the primary key of a row of `U` matching `filter`.
+    pub fn in_filter<U: Table>(&self, filter: Filter<U>) -> Filter<T> {
+        Filter::In {
+            own_column_name: self.name,
+            other_column_name: U::primary_column().name,
+            other_table_name: U::table_name(),
+            filter: Box::new(filter.cast()),
+        }
+    }
 }
 
 impl<T: Table> Display for Column<T> {
@@ -73,7 +73,41 @@ impl AsDataType for String {
     }
 }
 
-impl AsDataType for Key {
+impl<T> AsDataType for Key<T> {
+    fn as_data_type() -> DataType {
+        DataTypeKind::Text.into()
+    }
+}
+
+impl AsDataType for bool {
+    fn as_data_type() -> DataType {
+        DataTypeKind::Integer.into()
+    }
+}
+
+impl AsDataType for f64 {
+    fn as_data_type() -> DataType {
+        DataTypeKind::Real.into()
+    }
+}
+
+impl AsDataType for Vec<u8> {
+    fn as_data_type() -> DataType {
+        DataTypeKind::Blob.into()
+    }
+}
+
+/// Stored as RFC 3339 text
+#[cfg(feature = "chrono")]
+impl AsDataType for chrono::DateTime<chrono::FixedOffset> {
+    fn as_data_type() -> DataType {
+        DataTypeKind::Text.into()
+    }
+}
+
+/// Stored as `YYYY-MM-DD` text
+#[cfg(feature = "chrono")]
+impl AsDataType for chrono::NaiveDate {
     fn as_data_type() -> DataType {
         DataTypeKind::Text.into()
     }
@@ -0,0 +1,22 @@
+/// An index over one or more columns of a table.
+#[derive(Clone, Debug)]
+pub struct Index {
+    pub columns: &'static [&'static str],
+    pub unique: bool,
+}
+
+impl Index {
+    pub const fn new(columns: &'static [&'static str], unique: bool) -> Self {
+        Self { columns, unique }
+    }
+
+    /// The name is derived from the columns, so a changed index gets a new name.
+    pub fn name(&self, table_name: &str) -> String {
+        format!(
+            "{}_{}_{}",
+            table_name,
+            self.columns.join("_"),
+            if self.unique { "unique" } else { "index" }
+        )
+    }
+}
@@ -1,10 +1,20 @@
 mod table;
 mod column;
+mod row;
+mod value;
+mod index;
+mod primary_key;
 mod data_type;
 pub mod modifier;
 
 pub use table::*;
 pub use column::Column;
+pub(crate) use column::DELETED_AT_COLUMN;
+pub use row::{FromRow, Row};
+pub use value::FromValue;
+pub use index::Index;
+pub use primary_key::PrimaryKey;
+pub(crate) use primary_key::primary_names;
 pub use data_type::DataType;
 pub use data_type::DataTypeKind;
 pub use data_type::AsDataType;
\ No newline at end of file
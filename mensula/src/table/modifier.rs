@@ -1,11 +1,17 @@
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
+use crate::FilterValue;
+
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct Modifier {
     pub unique: bool,
     pub primary: bool,
     pub reference: Option<ForeignReference>,
+    /// The default value, rejected by [`crate::Database::register`] if it has no SQL literal
+    pub default: Option<FilterValue>,
+    /// A SQL expression that needs to be true for every row
+    pub check: Option<String>,
 }
 
 impl Modifier {
@@ -14,11 +20,31 @@ impl Modifier {
             unique,
             primary,
             reference,
+            default: None,
+            check: None,
+        }
+    }
+
+    pub fn with_default(self, value: FilterValue) -> Self {
+        Self {
+            default: Some(value),
+            ..self
+        }
+    }
+
+    pub fn with_check(self, check: &str) -> Self {
+        Self {
+            check: Some(check.to_owned()),
+            ..self
         }
     }
 
     pub fn has_content(&self) -> bool {
-        self.unique || self.primary || self.reference.is_some()
+        self.unique
+            || self.primary
+            || self.reference.is_some()
+            || self.default.is_some()
+            || self.check.is_some()
     }
 }
 
@@ -77,6 +103,13 @@ impl Display for Modifier {
                 reference.on_delete.as_ref()
             )?;
         }
+        if let Some(default) = &self.default {
+            let default = default.to_sql().map_err(|_| std::fmt::Error)?;
+            write!(f, " DEFAULT {}", default)?;
+        }
+        if let Some(check) = &self.check {
+            write!(f, " CHECK ({})", check)?;
+        }
         Ok(())
     }
 }
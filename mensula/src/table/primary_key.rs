@@ -0,0 +1,71 @@
+use crate::{FilterValue, FromValue, Key, Result, Row, Table};
+
+/// The value of the primary key of a row.
+///
+/// A single value for tables with one `#[primary]` column,
+/// or a tuple with a value for each column of a composite primary key.
+pub trait PrimaryKey: Clone + Send + Sized + 'static {
+    /// The values in the order of [`crate::Table::primary_columns`]
+    fn into_values(self) -> Vec<FilterValue>;
+
+    /// Reads the key from the first columns of the row.
+    fn read(row: &Row) -> Result<Self>;
+}
+
+/// The names of the primary columns of `T`, separated by commas
+pub(crate) fn primary_names<T: Table>() -> String {
+    T::primary_columns()
+        .iter()
+        .map(|column| column.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+macro_rules! impl_primary_key {
+    ($($ty:ty),+) => {
+        $(
+        impl PrimaryKey for $ty {
+            fn into_values(self) -> Vec<FilterValue> {
+                vec![self.into()]
+            }
+
+            fn read(row: &Row) -> Result<Self> {
+                row.read(0)
+            }
+        }
+        )+
+    };
+}
+
+impl_primary_key!(i64, String);
+
+impl<T: 'static> PrimaryKey for Key<T> {
+    fn into_values(self) -> Vec<FilterValue> {
+        vec![self.into()]
+    }
+
+    fn read(row: &Row) -> Result<Self> {
+        row.read(0)
+    }
+}
+
+macro_rules! impl_composite_primary_key {
+    ($($name:ident: $index:tt),+) => {
+        impl<$($name),+> PrimaryKey for ($($name,)+)
+        where
+            $($name: Into<FilterValue> + FromValue + Clone + Send + 'static),+
+        {
+            fn into_values(self) -> Vec<FilterValue> {
+                vec![$(self.$index.into()),+]
+            }
+
+            fn read(row: &Row) -> Result<Self> {
+                Ok(($(row.read::<$name>($index)?,)+))
+            }
+        }
+    };
+}
+
+impl_composite_primary_key!(A: 0, B: 1);
+impl_composite_primary_key!(A: 0, B: 1, C: 2);
+impl_composite_primary_key!(A: 0, B: 1, C: 2, D: 3);
@@ -0,0 +1,69 @@
+use sqlite::{Statement, Value};
+
+use crate::{Error, FromValue, Result};
+
+/// The current row of a query, which can only be read.
+///
+/// Wraps the statement of the query instead of handing it out,
+/// because the statements are cached and sent to other threads with their connection,
+/// see [`crate::Database`].
+pub struct Row<'a> {
+    statement: &'a Statement<'a>,
+}
+
+impl<'a> Row<'a> {
+    pub(crate) fn new(statement: &'a Statement<'a>) -> Self {
+        Self { statement }
+    }
+
+    /// Reads the column at `index`.
+    pub fn read<V: FromValue>(&self, index: usize) -> Result<V> {
+        let column = || {
+            self.statement
+                .column_names()
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| index.to_string())
+        };
+
+        decode(self.statement.read::<Value, _>(index).ok(), column)
+    }
+
+    /// Reads the column with the name `name`.
+    pub fn read_named<V: FromValue>(&self, name: &str) -> Result<V> {
+        decode(self.statement.read::<Value, _>(name).ok(), || name.to_owned())
+    }
+}
+
+fn decode<V: FromValue>(value: Option<Value>, column: impl FnOnce() -> String) -> Result<V> {
+    value
+        .and_then(V::from_value)
+        .ok_or_else(|| Error::Decode { column: column() })
+}
+
+/// A row that is read by column index, e.g. the result of an aggregate query.
+///
+/// Implemented for tuples and can be derived for structs with `#[derive(FromRow)]`,
+/// which reads the fields in the order they are declared.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self>;
+}
+
+macro_rules! impl_from_row {
+    ($($name:ident: $index:tt),+) => {
+        impl<$($name: FromValue),+> FromRow for ($($name,)+) {
+            fn from_row(row: &Row) -> Result<Self> {
+                Ok(($(row.read::<$name>($index)?,)+))
+            }
+        }
+    };
+}
+
+impl_from_row!(A: 0);
+impl_from_row!(A: 0, B: 1);
+impl_from_row!(A: 0, B: 1, C: 2);
+impl_from_row!(A: 0, B: 1, C: 2, D: 3);
+impl_from_row!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_from_row!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_from_row!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_from_row!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
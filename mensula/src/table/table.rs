@@ -1,29 +1,52 @@
-use sqlite::Statement;
-
-use crate::Column;
+use crate::{Column, FilterValue, Index, PrimaryKey, Result, Row};
 
 pub trait Table
 where
     Self: Sized,
 {
+    /// The type of the primary key, a tuple for a composite primary key
+    type Primary: PrimaryKey;
+
     fn table_name() -> &'static str;
 
+    /// The primary column, or the first column of a composite primary key
     fn primary_column() -> Column<Self>;
+
+    /// All columns of the primary key, in the order of the values of [`Self::Primary`]
+    fn primary_columns() -> Vec<Column<Self>> {
+        vec![Self::primary_column()]
+    }
+
     fn get_columns() -> Vec<Column<Self>>;
 
+    fn get_indices() -> Vec<Index> {
+        Vec::new()
+    }
+
+    /// The columns that are searchable with a [`crate::query::SearchQuery`]
+    fn get_fulltext_columns() -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Whether deleting a row only sets its [`crate::Column::deleted_at`] column
+    fn is_soft_delete() -> bool {
+        false
+    }
+
     // fn get_primary(&self) -> Key;
 }
 
 pub trait Readable<R> {
     fn get_column_names() -> Option<&'static [&'static str]>;
-    fn read(statement: &sqlite::Statement) -> Option<R>;
+    fn read(row: &Row) -> Result<R>;
 }
 
 pub trait Insertable<T: Table> {
     fn get_column_names() -> &'static [&'static str];
     fn get_placeholder_names() -> &'static [&'static str];
 
-    fn bind(self, statement: &mut Statement) -> sqlite::Result<()>;
+    /// The values of the columns, in the order of [`Self::get_column_names`]
+    fn into_values(self) -> Vec<FilterValue>;
 }
 
 pub trait Link<T: Table> {
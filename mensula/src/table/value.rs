@@ -0,0 +1,85 @@
+use mensula_key::Key;
+use sqlite::Value;
+
+/// A type that can be read from the value of a column.
+///
+/// Should be implemented for every type that implements [`crate::AsDataType`].
+pub trait FromValue: Sized {
+    /// Returns `None` if the value has the wrong type or can not be parsed.
+    fn from_value(value: Value) -> Option<Self>;
+}
+
+impl FromValue for i64 {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Integer(int) => Some(int),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Float(real) => Some(real),
+            Value::Integer(int) => Some(int as f64),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Integer(int) => Some(int != 0),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::String(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Binary(blob) => Some(blob),
+            _ => None,
+        }
+    }
+}
+
+impl<T> FromValue for Key<T> {
+    fn from_value(value: Value) -> Option<Self> {
+        String::from_value(value).map(Key::from)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromValue for chrono::DateTime<chrono::FixedOffset> {
+    fn from_value(value: Value) -> Option<Self> {
+        chrono::DateTime::parse_from_rfc3339(&String::from_value(value)?).ok()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromValue for chrono::NaiveDate {
+    fn from_value(value: Value) -> Option<Self> {
+        String::from_value(value)?.parse().ok()
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::ops::Deref;
+
+use crate::{error::Result, Database};
+
+/// A running transaction or savepoint on a [`Database`].
+///
+/// If the transaction is dropped without being committed (e.g. because of a panic),
+/// it is rolled back.
+pub(crate) struct Transaction<'a> {
+    database: &'a Database,
+    depth: usize,
+    /// The number of changes recorded before the transaction started
+    change_mark: usize,
+    finished: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn begin(database: &'a Database) -> Result<Self> {
+        let depth = database.transaction_depth.get();
+
+        if depth == 0 {
+            // Takes the write lock right away, so a concurrent writer makes this wait
+            // for the busy timeout instead of failing when the first write happens
+            database.execute("BEGIN IMMEDIATE")?;
+        } else {
+            database.execute(format!("SAVEPOINT {}", Self::savepoint_name(depth)))?;
+        }
+
+        database.transaction_depth.set(depth + 1);
+
+        Ok(Self {
+            database,
+            depth,
+            change_mark: database.change_mark(),
+            finished: false,
+        })
+    }
+
+    fn savepoint_name(depth: usize) -> String {
+        format!("mensula_savepoint_{}", depth)
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+        self.database.transaction_depth.set(self.depth);
+    }
+
+    /// Commits the transaction and calls the observers of the changes made in it.
+    ///
+    /// The changes of a savepoint are reported when the outermost transaction is committed.
+    pub(crate) fn commit(mut self) -> Result<()> {
+        if self.depth > 0 {
+            let result = self
+                .database
+                .execute(format!("RELEASE {}", Self::savepoint_name(self.depth)));
+
+            // A failed commit is rolled back when the transaction is dropped
+            if result.is_ok() {
+                self.finish();
+            }
+
+            return result;
+        }
+
+        let database = self.database;
+        let notifications = database.prepare_notifications()?;
+
+        database.execute("COMMIT")?;
+        self.finish();
+
+        for notification in notifications {
+            notification();
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn rollback(mut self) -> Result<()> {
+        self.rollback_inner()
+    }
+
+    fn rollback_inner(&mut self) -> Result<()> {
+        self.finish();
+        self.database.discard_changes(self.change_mark);
+
+        if self.depth == 0 {
+            self.database.execute("ROLLBACK")
+        } else {
+            let name = Self::savepoint_name(self.depth);
+            self.database.execute(format!("ROLLBACK TO {}", name))?;
+            self.database.execute(format!("RELEASE {}", name))
+        }
+    }
+}
+
+impl Deref for Transaction<'_> {
+    type Target = Database;
+
+    fn deref(&self) -> &Self::Target {
+        self.database
+    }
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.rollback_inner();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Database, Error, Key, Table};
+
+    #[derive(Table)]
+    pub struct Note {
+        #[primary]
+        id: Key<Self>,
+        text: String,
+    }
+
+    fn database() -> Database {
+        let mut database = Database::open_in_memory().unwrap();
+        database.register::<Note>().unwrap();
+        database
+    }
+
+    fn note(text: &str) -> Note {
+        Note {
+            id: Key::generate(),
+            text: text.to_owned(),
+        }
+    }
+
+    fn texts(database: &Database) -> Vec<String> {
+        let mut texts = database
+            .get_all::<Note>()
+            .unwrap()
+            .into_iter()
+            .map(|note| note.text)
+            .collect::<Vec<_>>();
+        texts.sort();
+        texts
+    }
+
+    #[test]
+    fn failed_savepoint_keeps_the_changes_of_the_outer_transaction() {
+        let database = database();
+
+        database
+            .transaction(|transaction| {
+                transaction.insert(note("outer"))?;
+
+                let inner = transaction.transaction(|transaction| {
+                    transaction.insert(note("inner"))?;
+                    Err::<(), _>(Error::NotFound)
+                });
+                assert!(matches!(inner, Err(Error::NotFound)));
+
+                transaction.insert(note("after"))?;
+                Ok::<_, Error>(())
+            })
+            .unwrap();
+
+        assert_eq!(texts(&database), vec!["after", "outer"]);
+    }
+
+    #[test]
+    fn failed_outer_transaction_discards_a_released_savepoint() {
+        let database = database();
+
+        let result = database.transaction(|transaction| {
+            transaction.transaction(|transaction| transaction.insert(note("inner")).map(|_| ()))?;
+            Err::<(), _>(Error::NotFound)
+        });
+
+        assert!(matches!(result, Err(Error::NotFound)));
+        assert!(texts(&database).is_empty());
+    }
+}
@@ -0,0 +1,53 @@
+use proc_macro::TokenStream;
+use syn::{Error, Fields};
+
+/// Stores unit-only enums as their discriminant in an `INTEGER` column.
+pub fn impl_data_type(ast: syn::DeriveInput) -> Result<TokenStream, Error> {
+    let name = &ast.ident;
+
+    let variants = match &ast.data {
+        syn::Data::Enum(data) => &data.variants,
+        _ => Err(Error::new_spanned(name, "Expected Enum"))?,
+    };
+
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            Err(Error::new_spanned(variant, "Expected Unit Variant"))?;
+        }
+    }
+
+    let variants = variants.iter().map(|variant| &variant.ident);
+    let variants2 = variants.clone();
+
+    Ok(quote! {
+      #[automatically_derived]
+      impl mensula::AsDataType for #name {
+        fn as_data_type() -> mensula::DataType {
+          <i64 as mensula::AsDataType>::as_data_type()
+        }
+      }
+
+      #[automatically_derived]
+      impl mensula::FromValue for #name {
+        fn from_value(value: mensula::sqlite::Value) -> Option<Self> {
+          let value = <i64 as mensula::FromValue>::from_value(value)?;
+
+          #(
+            if value == #name::#variants as i64 {
+              return Some(#name::#variants2);
+            }
+          )*
+
+          None
+        }
+      }
+
+      #[automatically_derived]
+      impl From<#name> for mensula::FilterValue {
+        fn from(value: #name) -> Self {
+          mensula::FilterValue::Int(value as i64)
+        }
+      }
+    }
+    .into())
+}
@@ -1,5 +1,8 @@
 mod table;
 mod quotes;
+mod data_type;
+mod projection;
+mod row;
 
 extern crate proc_macro;
 extern crate syn;
@@ -8,8 +11,11 @@ extern crate quote;
 
 use proc_macro::TokenStream;
 use quotes::impl_table;
+use row::impl_from_row;
+use data_type::impl_data_type;
+use projection::impl_projection;
 
-#[proc_macro_derive(Table, attributes(table_name, primary, unique, foreign, on_update, on_delete, foreign_link))]
+#[proc_macro_derive(Table, attributes(table_name, soft_delete, primary, unique, foreign, on_update, on_delete, foreign_link, index, fulltext, unique_together, default, check, column_name))]
 pub fn derive_table(input: TokenStream) -> TokenStream {
   let result = parse(input);
   match result {
@@ -18,6 +24,33 @@ pub fn derive_table(input: TokenStream) -> TokenStream {
   }
 }
 
+#[proc_macro_derive(FromRow)]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+  let result = syn::parse(input).and_then(impl_from_row);
+  match result {
+    Ok(result) => result,
+    Err(error) => to_compile_errors(error),
+  }
+}
+
+#[proc_macro_derive(AsDataType)]
+pub fn derive_data_type(input: TokenStream) -> TokenStream {
+  let result = syn::parse(input).and_then(impl_data_type);
+  match result {
+    Ok(result) => result,
+    Err(error) => to_compile_errors(error),
+  }
+}
+
+#[proc_macro_derive(Projection, attributes(projection))]
+pub fn derive_projection(input: TokenStream) -> TokenStream {
+  let result = syn::parse(input).and_then(impl_projection);
+  match result {
+    Ok(result) => result,
+    Err(error) => to_compile_errors(error),
+  }
+}
+
 fn parse(input: TokenStream) -> Result<TokenStream, syn::Error> {
   let ast = syn::parse(input)?;
 
@@ -0,0 +1,57 @@
+use proc_macro::TokenStream;
+use syn::{Error, Type};
+
+/// Implements `Readable<Projection>` for the table given with `#[projection(of = Table)]`.
+///
+/// The fields are matched with the columns of the table by name. The column accessors
+/// of the table are used to get the column names, so unknown fields fail to compile.
+pub fn impl_projection(ast: syn::DeriveInput) -> Result<TokenStream, Error> {
+    let name = &ast.ident;
+
+    let mut table = None;
+
+    for attr in &ast.attrs {
+        if attr.path().is_ident("projection") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("of") {
+                    table = Some(meta.value()?.parse::<Type>()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("Expected 'of'"))
+                }
+            })?;
+        }
+    }
+
+    let table = table.ok_or_else(|| Error::new_spanned(name, "Expected #[projection(of = Table)]"))?;
+
+    let fields = match ast.data {
+        syn::Data::Struct(data) => match data.fields {
+            syn::Fields::Named(fields) => fields.named,
+            _ => Err(Error::new_spanned(name, "Expected Named Fields"))?,
+        },
+        _ => Err(Error::new_spanned(name, "Expected Struct"))?,
+    };
+
+    let idents: Vec<_> = fields.iter().filter_map(|field| field.ident.clone()).collect();
+    let idents2 = idents.clone();
+    let idents3 = idents.clone();
+
+    Ok(quote! {
+      #[automatically_derived]
+      impl mensula::Readable<#name> for #table {
+        fn get_column_names() -> Option<&'static [&'static str]> {
+          static NAMES: std::sync::OnceLock<Vec<&'static str>> = std::sync::OnceLock::new();
+
+          Some(NAMES.get_or_init(|| vec![#(<#table>::#idents().name,)*]).as_slice())
+        }
+
+        fn read(row: &mensula::Row) -> mensula::Result<#name> {
+          Ok(#name {
+            #(#idents2: row.read_named(<#table>::#idents3().name)?,)*
+          })
+        }
+      }
+    }
+    .into())
+}
@@ -1,14 +1,28 @@
 use proc_macro::TokenStream;
-use syn::{Error, Expr, Ident, Lit, ExprLit};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Error, Expr, Ident, Lit, ExprLit, Token, Type};
 
 use crate::table::Column;
 
 pub fn impl_table(ast: syn::DeriveInput) -> Result<TokenStream, Error> {
     let mut table_name = None;
+    let mut indices = vec![];
+    let mut soft_delete = false;
 
     for attr in ast.attrs {
         if let Some(ident) = attr.path().get_ident() {
-            if ident.to_string() == "table_name" {
+            if ident == "index" || ident == "unique_together" {
+                let columns = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+
+                if columns.is_empty() {
+                    Err(Error::new_spanned(&attr, "Expected at least one column"))?
+                }
+
+                indices.push((columns.into_iter().collect::<Vec<_>>(), ident == "unique_together"));
+            } else if ident == "soft_delete" {
+                soft_delete = true;
+            } else if ident == "table_name" {
                 let expr: Expr = attr.parse_args()?;
 
                 if let Expr::Lit(ExprLit {
@@ -25,7 +39,7 @@ pub fn impl_table(ast: syn::DeriveInput) -> Result<TokenStream, Error> {
     let name = &ast.ident;
     let table_name = table_name.unwrap_or_else(|| name.to_string());
 
-    let mut primary = None;
+    let mut primary = vec![];
     let mut columns = vec![];
 
     match ast.data {
@@ -42,13 +56,60 @@ pub fn impl_table(ast: syn::DeriveInput) -> Result<TokenStream, Error> {
         _ => Err(Error::new_spanned(name, "Expected Struct"))?,
     }
 
-    let primary = primary.ok_or_else(|| Error::new_spanned(name, "No primary field set"))?;
+    if primary.is_empty() {
+        Err(Error::new_spanned(name, "No primary field set"))?
+    }
+
+    // Key columns referencing a table need to say which one, so the reference can be checked
+    if let Some(column) = columns
+        .iter()
+        .find(|c| (c.modifier.primary || c.modifier.reference.is_some()) && c.is_untyped_key())
+    {
+        Err(Error::new_spanned(
+            &column.field_type,
+            "Primary and foreign key columns need a typed key, like `Key<Self>` or `Key<Table>`",
+        ))?
+    }
+
+    if soft_delete {
+        if let Some(column) = columns.iter().find(|c| c.name == "deleted_at") {
+            Err(Error::new_spanned(
+                &column.ident,
+                "The column 'deleted_at' is added by #[soft_delete]",
+            ))?
+        }
+    }
+
+    let mut indices = indices
+        .into_iter()
+        .map(|(index_columns, unique)| {
+            let names = index_columns
+                .iter()
+                .map(|ident| {
+                    columns
+                        .iter()
+                        .find(|c| &c.ident == ident)
+                        .map(|c| c.name.clone())
+                        .ok_or_else(|| Error::new_spanned(ident, format!("Unknown column '{}'", ident)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok((names, unique))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    for column in &columns {
+        if column.index {
+            indices.push((vec![column.name.clone()], false));
+        }
+    }
 
-    let table_impl_quote = impl_quote(name, &table_name, &columns, &primary);
+    let table_impl_quote = impl_quote(name, &table_name, &columns, &primary, &indices, soft_delete);
     let insert_impl_quote = insert_quote(name, &columns);
-    let columns_impl_quote = columns_quote(name, &columns);
+    let columns_impl_quote = columns_quote(name, &columns, soft_delete);
     let link_impl_quote = link_quote(name, &columns);
     let read_impl_quote = read_quote(name, &columns, &primary);
+    let key_check_quote = key_check_quote(name, &columns);
 
     Ok(quote! {
       #insert_impl_quote
@@ -60,17 +121,18 @@ pub fn impl_table(ast: syn::DeriveInput) -> Result<TokenStream, Error> {
       #link_impl_quote
 
       #read_impl_quote
+
+      #key_check_quote
     }
     .into())
 }
 
-fn insert_quote(name: &Ident, columns: &Vec<Column>) -> quote::__private::TokenStream {
+fn insert_quote(name: &Ident, columns: &[Column]) -> quote::__private::TokenStream {
     let columns = columns.iter();
     let idents = columns.clone().map(|c| c.ident.clone());
 
-    let column_names = columns.clone().map(|c| c.ident.to_string());
+    let column_names = columns.clone().map(|c| c.name.clone());
     let placeholder_names = column_names.clone().map(|name| format!(":{}", name));
-    let placeholder_names2 = placeholder_names.clone();
 
     quote!(
       impl mensula::Insertable<#name> for #name {
@@ -87,21 +149,27 @@ fn insert_quote(name: &Ident, columns: &Vec<Column>) -> quote::__private::TokenS
           ]
         }
 
-        fn bind(self, statement: &mut mensula::sqlite::Statement) -> mensula::sqlite::Result<()> {
-          statement.bind_iter::<_, (_, mensula::sqlite::Value)>([
-            #(
-              (#placeholder_names2, Into::<mensula::FilterValue>::into(self.#idents).into()),
-            )*
-          ])
+        fn into_values(self) -> Vec<mensula::FilterValue> {
+          vec![
+            #(Into::<mensula::FilterValue>::into(self.#idents),)*
+          ]
         }
       }
     )
 }
 
-fn columns_quote(name: &Ident, columns: &Vec<Column>) -> quote::__private::TokenStream {
+fn columns_quote(name: &Ident, columns: &[Column], soft_delete: bool) -> quote::__private::TokenStream {
     let columns = columns.iter();
     let column_names = columns.clone().map(|c| c.ident.clone());
 
+    let deleted_at = soft_delete.then(|| {
+        quote!(
+          pub fn deleted_at() -> mensula::Column<Self> {
+            mensula::Column::deleted_at()
+          }
+        )
+    });
+
     quote!(
       impl #name {
         #(
@@ -109,6 +177,8 @@ fn columns_quote(name: &Ident, columns: &Vec<Column>) -> quote::__private::Token
             #columns
           }
         )*
+
+        #deleted_at
       }
     )
 }
@@ -116,28 +186,66 @@ fn columns_quote(name: &Ident, columns: &Vec<Column>) -> quote::__private::Token
 fn impl_quote(
     name: &Ident,
     table_name: &str,
-    columns: &Vec<Column>,
-    primary: &Column,
+    columns: &[Column],
+    primary: &Vec<Column>,
+    indices: &[(Vec<String>, bool)],
+    soft_delete: bool,
 ) -> quote::__private::TokenStream {
     // let primary_ident = &primary.ident;
 
+    let primary_type = primary_type(primary);
+    let first_primary = &primary[0];
+
+    let index_columns = indices.iter().map(|(columns, _)| columns.iter());
+    let index_columns = index_columns.map(|names| quote!(&[#(#names,)*]));
+    let index_unique = indices.iter().map(|(_, unique)| unique);
+
+    let fulltext_names = columns.iter().filter(|c| c.fulltext).map(|c| &c.name);
+
+    let deleted_at = soft_delete.then(|| quote!(mensula::Column::deleted_at(),));
+
     quote!(
       #[automatically_derived]
       impl Table for #name {
+        type Primary = #primary_type;
+
         fn table_name() -> &'static str {
           #table_name
         }
 
         fn primary_column() -> mensula::Column<Self> {
-          #primary
+          #first_primary
+        }
+
+        fn primary_columns() -> Vec<mensula::Column<Self>> {
+          vec![
+            #(#primary,)*
+          ]
         }
 
         fn get_columns() -> Vec<mensula::Column<Self>> {
           vec![
             #(#columns,)*
+            #deleted_at
           ]
         }
 
+        fn get_indices() -> Vec<mensula::Index> {
+          vec![
+            #(mensula::Index::new(#index_columns, #index_unique),)*
+          ]
+        }
+
+        fn get_fulltext_columns() -> Vec<&'static str> {
+          vec![
+            #(#fulltext_names,)*
+          ]
+        }
+
+        fn is_soft_delete() -> bool {
+          #soft_delete
+        }
+
         // fn get_primary(&self) -> mensula::Key {
         //   self.#primary_ident.clone()
         // }
@@ -145,11 +253,11 @@ fn impl_quote(
     )
 }
 
-fn link_quote(name: &Ident, columns: &Vec<Column>) -> quote::__private::TokenStream {
+fn link_quote(name: &Ident, columns: &[Column]) -> quote::__private::TokenStream {
     let filter_columns = columns.iter().filter_map(|c| {
         if let Some(reference) = &c.modifier.reference {
             if reference.is_link {
-                return Some((&c.ident, &reference.ty));
+                return Some((&c.name, &reference.ty));
             }
         }
         None
@@ -157,7 +265,7 @@ fn link_quote(name: &Ident, columns: &Vec<Column>) -> quote::__private::TokenStr
 
     let name = filter_columns.clone().map(|_| name.clone());
     let link_types = filter_columns.clone().map(|(_, ty)| ty);
-    let link_names = filter_columns.clone().map(|(ident, _)| ident.to_string());
+    let link_names = filter_columns.clone().map(|(name, _)| name);
 
     quote!(
       #(
@@ -171,28 +279,121 @@ fn link_quote(name: &Ident, columns: &Vec<Column>) -> quote::__private::TokenStr
     )
 }
 
+/// Checks that every typed key column references the right table,
+/// which is the foreign table for foreign columns and the table itself for other primary columns.
+fn key_check_quote(name: &Ident, columns: &[Column]) -> quote::__private::TokenStream {
+    let checks = columns.iter().filter_map(|column| {
+        let found = resolve_self(column.key_table()?, name);
+
+        // Primary columns of link tables reference the linked table
+        let expected = match &column.modifier.reference {
+            Some(reference) => {
+                let ty = &reference.ty;
+                quote!(#ty)
+            }
+            None if column.modifier.primary => quote!(#name),
+            None => return None,
+        };
+
+        Some(quote_spanned!(column.field_type.span()=>
+          let _: ::std::marker::PhantomData<#expected> = ::std::marker::PhantomData::<#found>;
+        ))
+    });
+
+    quote!(
+      const _: fn() = || {
+        #(#checks)*
+      };
+    )
+}
+
+/// Replaces `Self` with the name of the table, for types used outside of its impl blocks
+fn resolve_self(ty: &Type, name: &Ident) -> Type {
+    match ty {
+        Type::Path(path) if path.qself.is_none() && path.path.is_ident("Self") => {
+            syn::parse_quote!(#name)
+        }
+        _ => ty.clone(),
+    }
+}
+
+/// The type of a single primary column, or a tuple of the types of a composite primary key
+fn primary_type(primary: &[Column]) -> quote::__private::TokenStream {
+    match primary {
+        [column] => {
+            let ty = &column.field_type;
+            quote!(#ty)
+        }
+        columns => {
+            let types = columns.iter().map(|c| &c.field_type);
+            quote!((#(#types,)*))
+        }
+    }
+}
+
 fn read_quote(
     name: &Ident,
-    columns: &Vec<Column>,
-    primary: &Column,
+    columns: &[Column],
+    primary: &Vec<Column>,
 ) -> quote::__private::TokenStream {
-    let primary_name = primary.ident.to_string();
+    let primary_quote = match primary.as_slice() {
+        [primary] => {
+            let primary_name = &primary.name;
+
+            // A typed primary key can be read as typed and as untyped key
+            let key_types = match primary.key_table() {
+                Some(table) => {
+                    let table = resolve_self(table, name);
+                    vec![quote!(mensula::Key), quote!(mensula::Key<#table>)]
+                }
+                None => vec![quote!(mensula::Key)],
+            };
+
+            quote!(
+              #(
+              #[automatically_derived]
+              impl mensula::Readable<#key_types> for #name {
+                fn get_column_names() -> Option<&'static [&'static str]> {
+                  Some(&[#primary_name])
+                }
+
+                fn read(row: &mensula::Row) -> mensula::Result<#key_types> {
+                  row.read_named(#primary_name)
+                }
+              }
+              )*
+            )
+        }
+        primary => {
+            let primary_type = primary_type(primary);
+            let primary_names = primary.iter().map(|c| &c.name);
+            let primary_names2 = primary_names.clone();
+
+            quote!(
+              #[automatically_derived]
+              impl mensula::Readable<#primary_type> for #name {
+                fn get_column_names() -> Option<&'static [&'static str]> {
+                  Some(&[
+                    #(#primary_names,)*
+                  ])
+                }
+
+                fn read(row: &mensula::Row) -> mensula::Result<#primary_type> {
+                  Ok((
+                    #(row.read_named(#primary_names2)?,)*
+                  ))
+                }
+              }
+            )
+        }
+    };
 
     let idents = columns.iter().map(|c| &c.ident);
-    let names = idents.clone().map(|i| i.to_string());
+    let names = columns.iter().map(|c| &c.name);
     let names2 = names.clone();
 
     quote!(
-      #[automatically_derived]
-      impl mensula::Readable<mensula::Key> for #name {
-        fn get_column_names() -> Option<&'static [&'static str]> {
-          Some(&[#primary_name])
-        }
-
-        fn read(statement: &mensula::sqlite::Statement) -> Option<mensula::Key> {
-          Some(statement.read(#primary_name).ok()?)
-        }
-      }
+      #primary_quote
 
       #[automatically_derived]
       impl mensula::Readable<Self> for #name {
@@ -202,9 +403,9 @@ fn read_quote(
           ])
         }
 
-        fn read(statement: &mensula::sqlite::Statement) -> Option<Self> {
-          Some(Self {
-            #(#idents: statement.read(#names2).ok()?,)*
+        fn read(row: &mensula::Row) -> mensula::Result<Self> {
+          Ok(Self {
+            #(#idents: row.read_named(#names2)?,)*
           })
         }
       }
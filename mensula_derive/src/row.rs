@@ -0,0 +1,39 @@
+use proc_macro::TokenStream;
+use syn::Error;
+
+pub fn impl_from_row(ast: syn::DeriveInput) -> Result<TokenStream, Error> {
+    let name = &ast.ident;
+
+    let fields = match ast.data {
+        syn::Data::Struct(data) => data.fields,
+        _ => Err(Error::new_spanned(name, "Expected Struct"))?,
+    };
+
+    let read_fields = match &fields {
+        syn::Fields::Named(fields) => {
+            let idents = fields.named.iter().map(|field| field.ident.clone());
+            let indices = 0..fields.named.len();
+
+            quote!({
+              #(#idents: row.read(#indices)?,)*
+            })
+        }
+        syn::Fields::Unnamed(fields) => {
+            let indices = 0..fields.unnamed.len();
+
+            quote!((
+              #(row.read(#indices)?,)*
+            ))
+        }
+        syn::Fields::Unit => Err(Error::new_spanned(name, "Expected Fields"))?,
+    };
+
+    Ok(quote! {
+      impl mensula::FromRow for #name {
+        fn from_row(row: &mensula::Row) -> mensula::Result<Self> {
+          Ok(Self #read_fields)
+        }
+      }
+    }
+    .into())
+}
@@ -1,5 +1,5 @@
 use quote::ToTokens;
-use syn::{Attribute, Error, Expr, Ident, Lit};
+use syn::{Attribute, Error, Expr, ExprLit, GenericArgument, Ident, Lit, PathArguments, PathSegment};
 use syn::{Field, Type};
 
 use super::{ForeignReference, ForeignRule, Modifier};
@@ -7,8 +7,13 @@ use super::{ForeignReference, ForeignRule, Modifier};
 #[derive(Clone)]
 pub struct Column {
     pub ident: Ident,
+    /// The name of the column in SQL, defaults to the name of the field
+    pub name: String,
     pub field_type: Type,
     pub modifier: Modifier,
+    pub index: bool,
+    /// Whether the column is searchable with a full-text search
+    pub fulltext: bool,
     pub field: Field,
 }
 
@@ -17,27 +22,41 @@ impl Column {
         modifier.primary = true;
     }
 
-    fn set_primary(&self, primary: &mut Option<Column>) -> Result<(), Error> {
+    fn set_primary(&self, primary: &mut Vec<Column>) {
         if self.modifier.primary {
-            if let Some(primary_column) = primary {
-                return Err(Error::new_spanned(
-                    self.ident.clone(),
-                    format!(
-                        "Primary field already defined ('{}')",
-                        primary_column.ident.to_string()
-                    ),
-                ));
-            } else {
-                *primary = Some(self.clone());
-            }
+            primary.push(self.clone());
         }
-        Ok(())
     }
 
     fn handle_unique(modifier: &mut Modifier) {
         modifier.unique = true;
     }
 
+    fn parse_str(attr: &Attribute) -> Result<String, Error> {
+        let expr: Expr = attr.parse_args()?;
+
+        if let Expr::Lit(ExprLit {
+            lit: Lit::Str(literal),
+            ..
+        }) = expr
+        {
+            Ok(literal.value())
+        } else {
+            Err(Error::new_spanned(expr, "Expected string literal"))
+        }
+    }
+
+    fn handle_default(modifier: &mut Modifier, attr: &Attribute) -> Result<(), Error> {
+        let expr: Expr = attr.parse_args()?;
+        modifier.default = Some(expr);
+        Ok(())
+    }
+
+    fn handle_check(modifier: &mut Modifier, attr: &Attribute) -> Result<(), Error> {
+        modifier.check = Some(Self::parse_str(attr)?);
+        Ok(())
+    }
+
     fn get_foreign_type(modifier: &Modifier, attr: &Attribute) -> Result<Type, Error> {
         if let Some(_) = modifier.reference {
             Err(Error::new_spanned(
@@ -96,11 +115,14 @@ impl Column {
         ))
     }
 
-    pub fn parse(mut field: Field, primary: &mut Option<Column>) -> Result<Self, Error> {
+    pub fn parse(mut field: Field, primary: &mut Vec<Column>) -> Result<Self, Error> {
         let field_ident = field.ident.clone().unwrap();
         let field_type = field.ty.clone();
 
         let mut modifier = Modifier::new();
+        let mut index = false;
+        let mut fulltext = false;
+        let mut column_name = field_ident.to_string();
 
         for attr in &field.attrs {
             let attr_ident = attr.path().get_ident();
@@ -110,6 +132,11 @@ impl Column {
                 match name.as_str() {
                     "primary" => Self::handle_primary(&mut modifier),
                     "unique" => Self::handle_unique(&mut modifier),
+                    "index" => index = true,
+                    "fulltext" => fulltext = true,
+                    "default" => Self::handle_default(&mut modifier, attr)?,
+                    "check" => Self::handle_check(&mut modifier, attr)?,
+                    "column_name" => column_name = Self::parse_str(attr)?,
                     "foreign_link" => Self::handle_foreign_link(&mut modifier, attr)?,
                     "foreign" => Self::handle_foreign(&mut modifier, attr)?,
                     "on_update" => Self::handle_foreign_rule(
@@ -129,20 +156,67 @@ impl Column {
 
         let column = Self {
             ident: field_ident,
+            name: column_name,
             field_type,
             modifier,
+            index,
+            fulltext,
             field,
         };
 
-        column.set_primary(primary)?;
+        column.set_primary(primary);
 
         Ok(column)
     }
 }
 
+impl Column {
+    /// The table `T` of a column of type `Key<T>` or `Option<Key<T>>`,
+    /// `None` for untyped keys and all other types.
+    pub fn key_table(&self) -> Option<&Type> {
+        match &self.key_segment()?.arguments {
+            PathArguments::AngleBracketed(arguments) => match arguments.args.first()? {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether the column is a `Key` or `Option<Key>` without the table it references
+    pub fn is_untyped_key(&self) -> bool {
+        self.key_segment()
+            .is_some_and(|segment| segment.arguments.is_none())
+    }
+
+    /// The last path segment of the key type of a column of type `Key` or `Option<Key>`
+    fn key_segment(&self) -> Option<&PathSegment> {
+        fn last_segment(ty: &Type) -> Option<&PathSegment> {
+            match ty {
+                Type::Path(path) => path.path.segments.last(),
+                _ => None,
+            }
+        }
+
+        let mut segment = last_segment(&self.field_type)?;
+
+        if segment.ident == "Option" {
+            segment = match &segment.arguments {
+                PathArguments::AngleBracketed(arguments) => match arguments.args.first()? {
+                    GenericArgument::Type(ty) => last_segment(ty)?,
+                    _ => return None,
+                },
+                _ => return None,
+            };
+        }
+
+        (segment.ident == "Key").then_some(segment)
+    }
+}
+
 impl ToTokens for Column {
     fn to_tokens(&self, tokens: &mut quote::__private::TokenStream) {
-        let name = &self.ident.to_string();
+        let name = &self.name;
         let field_type = &self.field_type;
         let modifier = &self.modifier;
 
@@ -1,12 +1,16 @@
 use quote::ToTokens;
 
+use syn::Expr;
+
 use super::ForeignReference;
 
 #[derive(Clone)]
 pub struct Modifier {
   pub unique: bool,
   pub primary: bool,
-  pub reference: Option<ForeignReference>
+  pub reference: Option<ForeignReference>,
+  pub default: Option<Expr>,
+  pub check: Option<String>,
 }
 
 impl Modifier {
@@ -15,6 +19,8 @@ impl Modifier {
       unique: false,
       primary: false,
       reference: None,
+      default: None,
+      check: None,
     }
   }
 }
@@ -28,8 +34,12 @@ impl ToTokens for Modifier {
     } else {
       quote!(None)
     };
+    let default = self.default.as_ref().map(|default| {
+      quote!(.with_default(mensula::FilterValue::from(#default)))
+    });
+    let check = self.check.as_ref().map(|check| quote!(.with_check(#check)));
     tokens.extend(quote!(
-      mensula::Modifier::new( #unique, #primary, #reference)
+      mensula::Modifier::new( #unique, #primary, #reference) #default #check
     ));
   }
 }
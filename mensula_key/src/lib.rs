@@ -1,4 +1,6 @@
-use std::fmt::Display;
+use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
@@ -6,30 +8,66 @@ use ulid::Ulid;
 // use sqlite::ReadableWithIndex;
 // use mensula::{table::DataTypeKind, AsDataType, DataType, FilterValue};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Key {
+/// The id of a row.
+///
+/// `Key<T>` can only reference rows of the table `T`,
+/// while a plain `Key` can reference rows of any table.
+/// Both are stored and serialized as the same string,
+/// so [`Key::erase`] and [`Key::cast`] only change the type.
+pub struct Key<T = Untyped> {
     id: String,
+    phantom: PhantomData<fn() -> T>,
 }
 
-impl AsRef<str> for Key {
+/// The table of a [`Key`] that is not bound to a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Untyped {}
+
+impl<T> Debug for Key<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Key").field("id", &self.id).finish()
+    }
+}
+
+impl<T> Clone for Key<T> {
+    fn clone(&self) -> Self {
+        Self::from(self.id.clone())
+    }
+}
+
+impl<T> PartialEq for Key<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Key<T> {}
+
+impl<T> Hash for Key<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> AsRef<str> for Key<T> {
     fn as_ref(&self) -> &str {
         &self.id
     }
 }
 
-impl Into<String> for Key {
-    fn into(self) -> String {
-        self.id
+impl<T> From<Key<T>> for String {
+    fn from(key: Key<T>) -> Self {
+        key.id
     }
 }
 
-impl Display for Key {
+impl<T> Display for Key<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_ref())
     }
 }
 
-impl Serialize for Key {
+impl<T> Serialize for Key<T> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer {
@@ -37,42 +75,64 @@ impl Serialize for Key {
     }
 }
 
-impl<'de> Deserialize<'de> for Key {
+impl<'de, T> Deserialize<'de> for Key<T> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de> {
         let id = String::deserialize(deserializer)?;
-        Ok(Self { id })
+        Ok(Self::from(id))
     }
 }
 
 impl Key {
     pub fn new() -> Self {
-        Self {
-            id: Ulid::new().to_string(),
-        }
+        Self::generate()
+    }
+}
+
+impl<T> Key<T> {
+    /// A new key for a row of `T`.
+    ///
+    /// Same as [`Key::new`], which is only defined for untyped keys,
+    /// so `Key::new()` keeps working where the table can not be inferred.
+    pub fn generate() -> Self {
+        Self::from(Ulid::new().to_string())
+    }
+
+    /// The same key, without the table it references
+    pub fn erase(self) -> Key {
+        self.cast()
+    }
+
+    /// The same key, referencing the table `U`.
+    ///
+    /// Nothing checks that the row exists in `U`, so this should only be used
+    /// for keys that are known to reference `U`, e.g. keys received from a client.
+    pub fn cast<U>(self) -> Key<U> {
+        Key::from(self.id)
     }
 }
 
-impl From<String> for Key {
+impl<T> From<String> for Key<T> {
     fn from(value: String) -> Self {
         Self {
-            id: value
+            id: value,
+            phantom: PhantomData,
         }
     }
 }
 
 #[cfg(feature = "sqlite")]
-impl sqlite::ReadableWithIndex for Key {
-    fn read<T: sqlite::ColumnIndex>(statement: &sqlite::Statement, index: T) -> sqlite::Result<Self> {
+impl<T> sqlite::ReadableWithIndex for Key<T> {
+    fn read<I: sqlite::ColumnIndex>(statement: &sqlite::Statement, index: I) -> sqlite::Result<Self> {
         let id = statement.read::<String, _>(index)?;
-        Ok(Self { id })
+        Ok(Self::from(id))
     }
 }
 
 #[cfg(feature = "sqlite")]
-impl sqlite::BindableWithIndex for Key {
-    fn bind<T: sqlite::ParameterIndex>(self, statement: &mut sqlite::Statement, index: T) -> sqlite::Result<()> {
+impl<T> sqlite::BindableWithIndex for Key<T> {
+    fn bind<I: sqlite::ParameterIndex>(self, statement: &mut sqlite::Statement, index: I) -> sqlite::Result<()> {
         let id: &str = &self.id;
         statement.bind((index, id))
     }
@@ -7,20 +7,30 @@ pub use super::data::*;
 use super::server;
 
 #[derive(Debug)]
-pub struct CategoryFetchError;
+pub enum CategoryFetchError {
+    NotFound,
+    Database(String),
+}
 
 #[derive(Debug)]
-pub struct CategoryAddError;
+pub enum CategoryAddError {
+    Database(String),
+}
 
 impl From<CategoryFetchError> for ServerFnError {
-    fn from(_: CategoryFetchError) -> Self {
-        ServerFnError::ServerError("could not get category".into())
+    fn from(value: CategoryFetchError) -> Self {
+        match value {
+            CategoryFetchError::NotFound => ServerFnError::ServerError("could not get category: not found".into()),
+            CategoryFetchError::Database(err) => ServerFnError::ServerError(format!("could not get category: {}", err)),
+        }
     }
 }
 
 impl From<CategoryAddError> for ServerFnError {
-    fn from(_: CategoryAddError) -> Self {
-        ServerFnError::ServerError("could not add category".into())
+    fn from(value: CategoryAddError) -> Self {
+        match value {
+            CategoryAddError::Database(err) => ServerFnError::ServerError(format!("could not add category: {}", err)),
+        }
     }
 }
 
@@ -38,31 +48,35 @@ pub async fn get_categories() -> Result<Vec<Category>, ServerFnError> {
 
 #[server]
 pub async fn get_categories_in_group(group_id: Key) -> Result<Vec<Category>, ServerFnError> {
-    server::get_categories_in_group(group_id).map_err(Into::into)
+    server::get_categories_in_group(group_id.cast()).map_err(Into::into)
 }
 
 // Get single
 
 #[server]
 pub async fn get_category(id: Key) -> Result<Category, ServerFnError> {
-    server::get_category(id).map_err(Into::into)
+    server::get_category(id.cast()).map_err(Into::into)
 }
 
 #[server]
 pub async fn get_category_group(id: Key) -> Result<CategoryGroup, ServerFnError> {
-    server::get_category_group(id).map_err(Into::into)
+    server::get_category_group(id.cast()).map_err(Into::into)
 }
 
 // Add single
 
 #[server]
 pub async fn add_category(name: String, icon: String, group: Key) -> Result<Key, ServerFnError> {
-    server::insert_category(None, name, icon, group).map_err(Into::into)
+    server::insert_category(None, name, icon, group.cast())
+        .map(Key::erase)
+        .map_err(Into::into)
 }
 
 #[server]
 pub async fn add_category_group(name: String, icon: String) -> Result<Key, ServerFnError> {
-    server::insert_category_group(None, name, icon).map_err(Into::into)
+    server::insert_category_group(None, name, icon)
+        .map(Key::erase)
+        .map_err(Into::into)
 }
 
 // Update
@@ -74,7 +88,9 @@ pub async fn update_category(
     icon: String,
     group: Key,
 ) -> Result<Key, ServerFnError> {
-    server::insert_category(Some(id), name, icon, group).map_err(Into::into)
+    server::insert_category(Some(id.cast()), name, icon, group.cast())
+        .map(Key::erase)
+        .map_err(Into::into)
 }
 
 #[server]
@@ -83,7 +99,9 @@ pub async fn update_category_group(
     name: String,
     icon: String,
 ) -> Result<Key, ServerFnError> {
-    server::insert_category_group(Some(id), name, icon).map_err(Into::into)
+    server::insert_category_group(Some(id.cast()), name, icon)
+        .map(Key::erase)
+        .map_err(Into::into)
 }
 
 // Delete
@@ -92,7 +110,7 @@ pub async fn update_category_group(
 pub async fn delete_category(
     id: Key
 ) -> Result<(), ServerFnError> {
-    if server::delete_category(id) {
+    if server::delete_category(id.cast()) {
         Ok(())
     } else {
         Err(ServerFnError::ServerError("Could not delete".to_string()))
@@ -103,7 +121,7 @@ pub async fn delete_category(
 pub async fn delete_category_group(
     id: Key
 ) -> Result<(), ServerFnError> {
-    if server::delete_category_group(id) {
+    if server::delete_category_group(id.cast()) {
         Ok(())
     } else {
         Err(ServerFnError::ServerError("Could not delete".to_string()))
@@ -11,21 +11,38 @@ use super::{
     data::{Category as ResponseCategory, CategoryGroup as ResponseCategoryGroup},
 };
 
+impl From<mensula::Error> for CategoryFetchError {
+    fn from(value: mensula::Error) -> Self {
+        match value {
+            mensula::Error::NotFound => CategoryFetchError::NotFound,
+            err => CategoryFetchError::Database(err.to_string()),
+        }
+    }
+}
+
+impl From<mensula::Error> for CategoryAddError {
+    fn from(value: mensula::Error) -> Self {
+        CategoryAddError::Database(value.to_string())
+    }
+}
+
 #[derive(Table)]
+#[soft_delete]
 pub struct Category {
     #[primary]
-    id: Key,
+    id: Key<Self>,
+    #[fulltext]
     name: String,
     icon: String,
-    // group is a reserved keyword in SQL, so this needs to be called group_id
     #[foreign(CategoryGroup)]
-    group_id: Key,
+    #[column_name("group_id")]
+    group: Key<CategoryGroup>,
 }
 
 #[derive(Table)]
 pub struct CategoryGroup {
     #[primary]
-    id: Key,
+    id: Key<Self>,
     name: String,
     icon: String,
 }
@@ -34,7 +51,7 @@ impl From<(CategoryGroup, Vec<Key>)> for ResponseCategoryGroup {
     fn from(value: (CategoryGroup, Vec<Key>)) -> Self {
         let (group, categories) = value;
         ResponseCategoryGroup {
-            id: group.id,
+            id: group.id.erase(),
             name: group.name,
             icon: group.icon,
             categories,
@@ -45,10 +62,10 @@ impl From<(CategoryGroup, Vec<Key>)> for ResponseCategoryGroup {
 impl From<Category> for ResponseCategory {
     fn from(value: Category) -> Self {
         Self {
-            id: value.id,
+            id: value.id.erase(),
             name: value.name,
             icon: value.icon,
-            group: value.group_id,
+            group: value.group.erase(),
         }
     }
 }
@@ -58,17 +75,15 @@ pub fn get_category_groups() -> Result<Vec<ResponseCategoryGroup>, CategoryFetch
 
     let groups = SelectQuery::new()
         .order_by(CategoryGroup::name(), Ordering::Ascending)
-        .get_all::<CategoryGroup>(&db)
-        .ok_or(CategoryFetchError)?;
+        .get_all::<CategoryGroup>(&db)?;
 
     let mut group_vec = Vec::new();
 
     for group in groups {
         let categories = SelectQuery::new()
-            .filter(Category::group_id().eq(group.id.clone()))
+            .filter(Category::group().eq(group.id.clone()))
             .order_by(Category::name(), Ordering::Ascending)
-            .get_all::<Key>(&db)
-            .ok_or(CategoryFetchError)?;
+            .get_all::<Key>(&db)?;
 
         group_vec.push((group, categories).into());
     }
@@ -81,87 +96,84 @@ pub fn get_categories() -> Result<Vec<ResponseCategory>, CategoryFetchError> {
 
     let categories = SelectQuery::new()
         .order_by(Category::name(), Ordering::Ascending)
-        .get_all::<Category>(&db)
-        .ok_or(CategoryFetchError)?;
+        .get_all::<Category>(&db)?;
 
     let categories = categories.into_iter().map(Into::into).collect();
 
     Ok(categories)
 }
 
-pub fn get_categories_in_group(group_id: Key) -> Result<Vec<ResponseCategory>, CategoryFetchError> {
+pub fn get_categories_in_group(group_id: Key<CategoryGroup>) -> Result<Vec<ResponseCategory>, CategoryFetchError> {
     let db = get_db();
 
     let categories = SelectQuery::new()
-        .filter(Category::group_id().eq(group_id))
+        .filter(Category::group().eq(group_id))
         .order_by(Category::name(), Ordering::Ascending)
         .get_all::<Category>(&db);
 
     categories
         .map(|categories| categories.into_iter().map(Into::into).collect())
-        .ok_or(CategoryFetchError)
+        .map_err(Into::into)
 }
 
-pub fn get_category_group(id: Key) -> Result<ResponseCategoryGroup, CategoryFetchError> {
+pub fn get_category_group(id: Key<CategoryGroup>) -> Result<ResponseCategoryGroup, CategoryFetchError> {
     let db = get_db();
 
     let group = db
-        .get::<CategoryGroup>(id.clone())
-        .ok_or(CategoryFetchError)?;
+        .get::<CategoryGroup>(id.clone())?;
 
     let categories = SelectQuery::new()
-        .filter(Category::group_id().eq(id))
-        .get_all::<Key>(&db)
-        .ok_or(CategoryFetchError)?;
+        .filter(Category::group().eq(id))
+        .get_all::<Key>(&db)?;
 
     Ok((group, categories).into())
 }
 
-pub fn get_category(id: Key) -> Result<ResponseCategory, CategoryFetchError> {
+pub fn get_category(id: Key<Category>) -> Result<ResponseCategory, CategoryFetchError> {
     get_db()
         .get::<Category>(id)
-        .ok_or(CategoryFetchError)
+        .map_err(Into::into)
         .map(Into::into)
 }
 
 pub fn insert_category(
-    id: Option<Key>,
+    id: Option<Key<Category>>,
     name: String,
     icon: String,
-    group: Key,
-) -> Result<Key, CategoryAddError> {
+    group: Key<CategoryGroup>,
+) -> Result<Key<Category>, CategoryAddError> {
     get_db()
         .insert(Category {
-            id: id.unwrap_or_else(Key::new),
+            id: id.unwrap_or_else(Key::generate),
             name,
             icon,
-            group_id: group,
+            group,
         })
-        .ok_or(CategoryAddError)
+        .map_err(Into::into)
 }
 
 pub fn insert_category_group(
-    id: Option<Key>,
+    id: Option<Key<CategoryGroup>>,
     name: String,
     icon: String,
-) -> Result<Key, CategoryAddError> {
+) -> Result<Key<CategoryGroup>, CategoryAddError> {
     get_db()
         .insert(CategoryGroup {
-            id: id.unwrap_or_else(Key::new),
+            id: id.unwrap_or_else(Key::generate),
             name,
             icon,
         })
-        .ok_or(CategoryAddError)
+        .map_err(Into::into)
 }
 
 pub fn delete_category_group(
-    id: Key
+    id: Key<CategoryGroup>
 ) -> bool {
     get_db().delete::<CategoryGroup>(id).is_ok()
 }
 
 pub fn delete_category(
-    id: Key
+    id: Key<Category>
 ) -> bool {
     get_db().delete::<Category>(id).is_ok()
 }
\ No newline at end of file
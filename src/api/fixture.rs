@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset};
+use mensula::Pool;
+use mensula_key::Key;
+
+use super::{
+    category::server::{insert_category, insert_category_group, Category},
+    payment::{
+        server::{insert_payment, Payment},
+        AddPaymentData,
+    },
+    register_tables,
+    user::server::{add_user, User},
+};
+use crate::db;
+
+/// The password of every user created by a [`Fixture`]
+pub const FIXTURE_PASSWORD: &str = "fixture_password";
+
+const FIXTURE_TIMESTAMP: &str = "2023-01-01T12:00:00+01:00";
+
+/// Builds an in-memory database with all tables registered and seeds it with data.
+///
+/// The database is injected with [`db::inject`], so the `api::*::server` functions
+/// use it on the current thread.
+///
+/// Users, categories and payments are referenced by their name
+/// and the keys they got are returned by [`Fixture::build`].
+#[derive(Default)]
+pub struct Fixture {
+    users: Vec<String>,
+    categories: Vec<String>,
+    payments: Vec<FixturePayment>,
+}
+
+pub struct FixturePayment {
+    name: String,
+    amount: i64,
+    timestamp: DateTime<FixedOffset>,
+    owner: String,
+    users: Vec<String>,
+    categories: Vec<String>,
+}
+
+/// The keys of the seeded data, by name
+pub struct Seeded {
+    pub users: HashMap<String, Key<User>>,
+    pub categories: HashMap<String, Key<Category>>,
+    pub payments: HashMap<String, Key<Payment>>,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a user, which needs to be a valid user name.
+    pub fn user(mut self, name: &str) -> Self {
+        self.users.push(name.to_owned());
+        self
+    }
+
+    pub fn category(mut self, name: &str) -> Self {
+        self.categories.push(name.to_owned());
+        self
+    }
+
+    pub fn payment(mut self, payment: FixturePayment) -> Self {
+        self.payments.push(payment);
+        self
+    }
+
+    /// Creates the database and inserts the data.
+    ///
+    /// Panics if anything fails, as it is meant to be used in tests.
+    pub fn build(self) -> Seeded {
+        let pool = Pool::open_in_memory().expect("could not open in-memory db");
+
+        register_tables(&mut pool.get().expect("could not open db")).unwrap();
+
+        db::inject(pool);
+
+        let users: HashMap<String, Key<User>> = self
+            .users
+            .into_iter()
+            .map(|name| {
+                let id = add_user(name.clone(), name.clone(), FIXTURE_PASSWORD.to_owned())
+                    .expect("could not add fixture user");
+                (name, id)
+            })
+            .collect();
+
+        let mut categories = HashMap::new();
+
+        if !self.categories.is_empty() {
+            let group = insert_category_group(None, "fixture".to_owned(), "".to_owned())
+                .expect("could not add fixture category group");
+
+            for name in self.categories {
+                let id = insert_category(None, name.clone(), "".to_owned(), group.clone())
+                    .expect("could not add fixture category");
+                categories.insert(name, id);
+            }
+        }
+
+        let mut payments = HashMap::new();
+
+        for payment in self.payments {
+            let data = AddPaymentData {
+                name: payment.name.clone(),
+                amount: payment.amount,
+                timestamp: payment.timestamp,
+                users: payment
+                    .users
+                    .iter()
+                    .map(|name| users[name].clone().erase())
+                    .collect(),
+                categories: payment
+                    .categories
+                    .iter()
+                    .map(|name| categories[name].clone().erase())
+                    .collect(),
+                tink: None,
+            };
+
+            let id = insert_payment(None, users[&payment.owner].clone(), data)
+                .expect("could not add fixture payment");
+            payments.insert(payment.name, id);
+        }
+
+        Seeded {
+            users,
+            categories,
+            payments,
+        }
+    }
+}
+
+impl FixturePayment {
+    /// A payment owned by and shared with `owner`
+    pub fn new(name: &str, amount: i64, owner: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            amount,
+            timestamp: DateTime::parse_from_rfc3339(FIXTURE_TIMESTAMP).unwrap(),
+            owner: owner.to_owned(),
+            users: vec![owner.to_owned()],
+            categories: Vec::new(),
+        }
+    }
+
+    pub fn timestamp(mut self, timestamp: &str) -> Self {
+        self.timestamp = DateTime::parse_from_rfc3339(timestamp).expect("invalid fixture timestamp");
+        self
+    }
+
+    /// Replaces the users the payment is shared with.
+    pub fn users(mut self, users: &[&str]) -> Self {
+        self.users = users.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    pub fn categories(mut self, categories: &[&str]) -> Self {
+        self.categories = categories.iter().map(|name| name.to_string()).collect();
+        self
+    }
+}
@@ -6,10 +6,10 @@ use mensula_key::Key;
 
 use crate::{
     api::{
-        category::server::{insert_category, insert_category_group},
-        payment::{server::insert_payment, AddPaymentData},
+        category::server::{insert_category, insert_category_group, Category},
+        payment::{server::{insert_payment, Payment}, AddPaymentData},
         rule::ShareRule,
-        rule::server::insert_rule,
+        rule::server::{insert_rule, Rule},
         tink::TinkPaymentData,
         user::server::User as NewUser,
     },
@@ -36,7 +36,7 @@ struct OldUser {
     auth_hash: String,
 }
 
-fn migrate_users(old_db: &mut Database) -> HashMap<i64, Key> {
+fn migrate_users(old_db: &mut Database) -> HashMap<i64, Key<NewUser>> {
     println!("Registering OldUser {:?}", old_db.register::<OldUser>());
 
     let old_users = old_db.get_all::<OldUser>().unwrap();
@@ -63,7 +63,7 @@ fn migrate_users(old_db: &mut Database) -> HashMap<i64, Key> {
     let new_db = db::get_db();
 
     for user in new_users {
-        new_db.insert(user);
+        new_db.insert(user).unwrap();
     }
 
     user_map
@@ -88,7 +88,7 @@ struct OldCategory {
     group_id: i64,
 }
 
-fn migrate_categories(old_db: &mut Database) -> HashMap<i64, Key> {
+fn migrate_categories(old_db: &mut Database) -> HashMap<i64, Key<Category>> {
     println!(
         "Registering OldCategoryGroup {:?}",
         old_db.register::<OldCategoryGroup>()
@@ -170,10 +170,10 @@ fn get_tink_payments(old_db: &mut Database) -> HashSet<i64> {
 
 fn migrate_payments(
     old_db: &mut Database,
-    user_map: &HashMap<i64, Key>,
-    category_map: &HashMap<i64, Key>,
+    user_map: &HashMap<i64, Key<NewUser>>,
+    category_map: &HashMap<i64, Key<Category>>,
     tink_payment_set: &HashSet<i64>,
-) -> HashMap<i64, Key> {
+) -> HashMap<i64, Key<Payment>> {
     println!("migrating payments");
     old_db.register::<OldPayment>().unwrap();
     old_db.register::<OldPaymentCategoryLink>().unwrap();
@@ -196,11 +196,11 @@ fn migrate_payments(
 
         let categories = categories
             .into_iter()
-            .map(|category| category_map[&category.category_id].clone())
+            .map(|category| category_map[&category.category_id].clone().erase())
             .collect();
         let users = users
             .into_iter()
-            .map(|user| user_map[&user.user_id].clone())
+            .map(|user| user_map[&user.user_id].clone().erase())
             .collect();
 
         let tink = tink_payment_set
@@ -258,7 +258,10 @@ struct OldRuleKeyword {
     rule_id: i64,
 }
 
-fn migrate_rules(old_db: &mut Database, category_map: &HashMap<i64, Key>) -> HashMap<i64, Key> {
+fn migrate_rules(
+    old_db: &mut Database,
+    category_map: &HashMap<i64, Key<Category>>,
+) -> HashMap<i64, Key<Rule>> {
     old_db.register::<OldRule>().unwrap();
     old_db.register::<OldRuleKeyword>().unwrap();
     old_db.register::<OldRuleCategoryLink>().unwrap();
@@ -6,15 +6,15 @@ pub mod user;
 
 #[cfg(feature = "ssr")]
 pub mod migrate;
+#[cfg(all(test, feature = "ssr"))]
+mod fixture;
 
 
-#[cfg(feature = "ssr")]
-use mensula::sqlite;
 #[cfg(feature = "ssr")]
 use mensula::Database;
 
 #[cfg(feature = "ssr")]
-pub fn register_tables(db: &mut Database) -> sqlite::Result<()> {
+pub fn register_tables(db: &mut Database) -> mensula::Result<()> {
 
     use self::user::server::User;
     use self::payment::server::{Payment, PaymentUserLink, PaymentCategoryLink};
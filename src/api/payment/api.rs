@@ -11,20 +11,38 @@ pub use super::data::*;
 use super::server;
 
 
-pub struct PaymentFetchError;
+#[derive(Debug)]
+pub enum PaymentFetchError {
+    NotFound,
+    InvalidData,
+    Database(String),
+}
 
 impl From<PaymentFetchError> for ServerFnError {
-    fn from(_: PaymentFetchError) -> Self {
-        Self::ServerError("could not get payment".to_owned())
+    fn from(value: PaymentFetchError) -> Self {
+        match value {
+            PaymentFetchError::NotFound => Self::ServerError("could not get payment: not found".to_owned()),
+            PaymentFetchError::InvalidData => Self::ServerError("could not get payment: invalid data".to_owned()),
+            PaymentFetchError::Database(err) => Self::ServerError(format!("could not get payment: {}", err)),
+        }
     }
 }
 
 
-pub struct PaymentUpdateError;
+#[derive(Debug)]
+pub enum PaymentUpdateError {
+    NotAllowed,
+    InvalidData,
+    Database(String),
+}
 
 impl From<PaymentUpdateError> for ServerFnError {
-    fn from(_: PaymentUpdateError) -> Self {
-        Self::ServerError("could not update payment".to_owned())
+    fn from(value: PaymentUpdateError) -> Self {
+        match value {
+            PaymentUpdateError::NotAllowed => Self::ServerError("could not update payment: not allowed".to_owned()),
+            PaymentUpdateError::InvalidData => Self::ServerError("could not update payment: invalid data".to_owned()),
+            PaymentUpdateError::Database(err) => Self::ServerError(format!("could not update payment: {}", err)),
+        }
     }
 }
 
@@ -69,7 +87,7 @@ pub async fn add_payments(payments: Vec<AddPaymentData>) -> Result<(), ServerFnE
     let user = crate::auth::get_user().await?;
 
     for payment in payments {
-        server::insert_payment(None, user.clone(), payment);
+        server::insert_payment(None, user.clone(), payment)?;
     }
 
     Ok(())
@@ -79,14 +97,26 @@ pub async fn add_payments(payments: Vec<AddPaymentData>) -> Result<(), ServerFnE
 pub async fn get_payment(id: Key) -> Result<Payment, ServerFnError> {
     let user = crate::auth::get_user().await?;
 
-    server::get_payment(user, id).map_err(Into::into)
+    server::get_payment(user, id.cast()).map_err(Into::into)
+}
+
+#[server]
+pub async fn search_payments(query: String, limit: usize) -> Result<Vec<Payment>, ServerFnError> {
+    let user = crate::auth::get_user().await?;
+
+    server::search_payments(user, query, limit).map_err(Into::into)
 }
 
 #[server]
 pub async fn payment_update_users(id: Key, users: Vec<Key>) -> Result<(), ServerFnError> {
     let request_user = crate::auth::get_user().await?;
 
-    server::payment_update_users(request_user, id, users).map_err(Into::into)
+    server::payment_update_users(
+        request_user,
+        id.cast(),
+        users.into_iter().map(Key::cast).collect(),
+    )
+    .map_err(Into::into)
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -101,11 +131,11 @@ pub async fn calculate_all_amounts() -> Result<AmountResult, ServerFnError> {
 
     let mut amounts = server::calculate_all_amounts().map_err(ServerFnError::from)?;
 
-    let (_, own_amount) = amounts.remove_entry(&user).ok_or_else(|| ServerFnError::from(PaymentFetchError))?;
+    let (_, own_amount) = amounts.remove_entry(&user).ok_or_else(|| ServerFnError::from(PaymentFetchError::NotFound))?;
 
     let others = amounts.into_iter().map(|(user, mut amount)| {
         amount.user_amount = 0;
-        (user, amount)
+        (user.erase(), amount)
     }).collect::<Vec<_>>();
 
     Ok(AmountResult { own: own_amount, others })
@@ -1,101 +1,142 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use chrono::DateTime;
-use mensula::query::{Ordering, SelectQuery};
-use mensula::{Database, Filter, Table};
+use chrono::{DateTime, Datelike, FixedOffset};
+use mensula::query::{AggregateQuery, DeleteQuery, Ordering, SearchQuery, SelectQuery};
+use mensula::{Database, Filter, Projection, Table};
 use mensula_key::Key;
 
 use crate::api::tink;
+use crate::api::tink::server::TinkPayment;
 use crate::api::{category::server::Category, tink::server::add_tink_payment, user::server::User};
 use crate::db::get_db;
 use crate::util::calculated_amount::CalculatedAmount;
-use crate::util::month::MonthDate;
+use crate::util::month::{Month, MonthDate};
 
 use super::api::{AddPaymentData, Payment as ResponsePayment, PaymentFetchError, PaymentUpdateError, PaymentMonthData};
 
+impl From<mensula::Error> for PaymentFetchError {
+    fn from(value: mensula::Error) -> Self {
+        match value {
+            mensula::Error::NotFound => PaymentFetchError::NotFound,
+            err => PaymentFetchError::Database(err.to_string()),
+        }
+    }
+}
+
+impl From<mensula::Error> for PaymentUpdateError {
+    fn from(value: mensula::Error) -> Self {
+        PaymentUpdateError::Database(value.to_string())
+    }
+}
+
 #[derive(Table)]
 pub struct Payment {
     #[primary]
-    id: Key,
+    id: Key<Self>,
+    #[fulltext]
     name: String,
     amount: i64,
-    timestamp: String,
+    #[index]
+    timestamp: DateTime<FixedOffset>,
     #[foreign(User)]
     #[on_delete("cascade")]
-    owner: Key,
+    owner: Key<User>,
+}
+
+/// The length of the year and month at the start of a timestamp, e.g. `2023-01`
+const MONTH_LENGTH: usize = 7;
+
+/// The columns of a [`Payment`] needed to calculate the share of one of its users.
+#[derive(Projection)]
+#[projection(of = Payment)]
+struct PaymentShareRow {
+    id: Key<Payment>,
+    amount: i64,
+    timestamp: DateTime<FixedOffset>,
 }
 
 #[derive(Table)]
 pub struct PaymentUserLink {
     #[primary]
-    id: Key,
     #[foreign_link(Payment)]
-    payment: Key,
+    payment: Key<Payment>,
+    #[primary]
     #[foreign_link(User)]
-    user: Key,
+    #[index]
+    user: Key<User>,
 }
 
 #[derive(Table)]
 pub struct PaymentCategoryLink {
     #[primary]
-    id: Key,
     #[foreign_link(Payment)]
-    payment: Key,
+    payment: Key<Payment>,
+    #[primary]
     #[foreign_link(Category)]
-    category: Key,
+    category: Key<Category>,
 }
 
-fn to_response_payment(
-    payment: Payment,
+fn to_response_payments(
+    payments: Vec<Payment>,
     db: &Database,
-) -> Result<ResponsePayment, PaymentFetchError> {
-    let users = SelectQuery::<User>::link::<Payment, PaymentUserLink>(payment.id.clone())
+) -> Result<Vec<ResponsePayment>, PaymentFetchError> {
+    let ids: Vec<Key<Payment>> = payments.iter().map(|payment| payment.id.clone()).collect();
+
+    let mut users = SelectQuery::<User>::new()
         .order_by(User::name(), Ordering::Ascending)
-        .get_all(&db)
-        .ok_or(PaymentFetchError)?;
-    let categories =
-        SelectQuery::<Category>::link::<Payment, PaymentCategoryLink>(payment.id.clone())
-            .order_by(Category::name(), Ordering::Ascending)
-            .get_all(&db)
-            .ok_or(PaymentFetchError)?;
-
-    let tink_payment = tink::server::get_payment_data(payment.id.clone(), Some(db));
-
-    Ok(ResponsePayment {
-        id: payment.id,
-        name: payment.name,
-        amount: payment.amount,
-        timestamp: DateTime::parse_from_rfc3339(&payment.timestamp)
-            .map_err(|_| PaymentFetchError)?,
-        owner: payment.owner,
-        users,
-        categories,
-        imported: tink_payment.is_some(),
-    })
+        .get_all_linked::<Payment, PaymentUserLink, _>(db, &ids)?;
+    let mut categories = SelectQuery::<Category>::new()
+        .order_by(Category::name(), Ordering::Ascending)
+        .get_all_linked::<Payment, PaymentCategoryLink, _>(db, &ids)?;
+
+    let imported = tink::server::get_imported_payments(ids, Some(db))?;
+
+    let payments = payments
+        .into_iter()
+        .map(|payment| {
+            let id = payment.id;
+
+            ResponsePayment {
+                users: users.remove(&id).unwrap_or_default(),
+                categories: categories.remove(&id).unwrap_or_default(),
+                imported: imported.contains(&id),
+                id: id.erase(),
+                name: payment.name,
+                amount: payment.amount,
+                timestamp: payment.timestamp,
+                owner: payment.owner.erase(),
+            }
+        })
+        .collect();
+
+    Ok(payments)
 }
 
-fn user_filter(user: &Key) -> Filter<Payment> {
+fn user_filter(user: &Key<User>) -> Filter<Payment> {
     Payment::owner()
         .eq(user.clone())
         .or(Payment::id().link::<PaymentUserLink, User>(user.clone()))
 }
 
-pub fn get_payment(user: Key, id: Key) -> Result<ResponsePayment, PaymentFetchError> {
+pub fn get_payment(user: Key<User>, id: Key<Payment>) -> Result<ResponsePayment, PaymentFetchError> {
     let db = get_db();
 
-    let payment = db.get::<Payment>(id).ok_or(PaymentFetchError)?;
+    let payment = db.get::<Payment>(id)?;
 
-    let payment = to_response_payment(payment, &db)?;
+    let payment = to_response_payments(vec![payment], &db)?
+        .pop()
+        .ok_or(PaymentFetchError::NotFound)?;
+    let user = user.erase();
 
     if payment.owner == user || payment.users.contains(&user) {
         Ok(payment)
     } else {
-        Err(PaymentFetchError)
+        Err(PaymentFetchError::NotFound)
     }
 }
 
 pub fn get_payments(
-    user: &Key,
+    user: &Key<User>,
     month: &MonthDate,
 ) -> Result<Vec<ResponsePayment>, PaymentFetchError> {
     let db = get_db();
@@ -107,166 +148,428 @@ pub fn get_payments(
                 .and(user_filter(&user)),
         )
         .order_by(Payment::timestamp(), Ordering::Descending)
-        .get_all::<Payment>(&db)
-        .ok_or(PaymentFetchError)?;
-
-    let mut response_payments = Vec::new();
-
-    for payment in payments {
-        response_payments.push(to_response_payment(payment, &db)?)
-    }
+        .get_all::<Payment>(&db)?;
 
-    Ok(response_payments)
+    to_response_payments(payments, &db)
 }
 
-pub fn get_months(user: Key) -> Result<Vec<PaymentMonthData>, PaymentFetchError> {
+pub fn get_months(user: Key<User>) -> Result<Vec<PaymentMonthData>, PaymentFetchError> {
     let db = get_db();
 
-    let payments = SelectQuery::new()
+    let month_counts = AggregateQuery::<Payment>::new()
+        .prefix(Payment::timestamp(), MONTH_LENGTH)
+        .count()
         .filter(user_filter(&user))
-        .get_all::<Payment>(&db)
-        .ok_or(PaymentFetchError)?;
+        .group_by_prefix(Payment::timestamp(), MONTH_LENGTH)
+        .get_all::<(String, i64)>(&db)?;
 
     let mut months = BTreeMap::<MonthDate, PaymentMonthData>::new();
 
-    for payment in payments {
-        let month: MonthDate = payment.timestamp.parse().map_err(|_| PaymentFetchError)?;
-
-        let users = SelectQuery::<User>::link::<Payment, PaymentUserLink>(payment.id)
-            .get_all(&db)
-            .ok_or(PaymentFetchError)?;
-        let is_owner = payment.owner == user;
-        let is_user = users.contains(&user);
-
-        let amount = CalculatedAmount::calculate(payment.amount, is_owner, is_user, users.len());
+    for (month, count) in month_counts {
+        let month = parse_month(&month)?;
 
         let month_data = months
             .entry(month.clone())
             .or_insert_with(|| PaymentMonthData::from(month));
+        month_data.payments_count = count as u32;
+    }
 
-        month_data.payments_count += 1;
-        month_data.amount += amount;
+    for has_users in [true, false] {
+        let owned_amounts = AggregateQuery::<Payment>::new()
+            .prefix(Payment::timestamp(), MONTH_LENGTH)
+            .sum(Payment::amount())
+            .filter(Payment::owner().eq(user.clone()).and(with_users(has_users)))
+            .group_by_prefix(Payment::timestamp(), MONTH_LENGTH)
+            .get_all::<(String, Option<i64>)>(&db)?;
+
+        for (month, amount) in owned_amounts {
+            if let Some(month_data) = months.get_mut(&parse_month(&month)?) {
+                month_data.amount += owned_amount(amount.unwrap_or(0), has_users);
+            }
+        }
+    }
+
+    let shared = SelectQuery::new()
+        .filter(Payment::id().link::<PaymentUserLink, User>(user.clone()))
+        .get_all::<PaymentShareRow>(&db)?;
+
+    let user_counts = AggregateQuery::<PaymentUserLink>::new()
+        .column(PaymentUserLink::payment())
+        .count()
+        .filter(PaymentUserLink::payment().in_values(shared.iter().map(|payment| payment.id.clone())))
+        .group_by(PaymentUserLink::payment())
+        .get_all::<(Key<Payment>, i64)>(&db)?;
+    let user_counts: HashMap<Key<Payment>, usize> = user_counts
+        .into_iter()
+        .map(|(payment, count)| (payment, count as usize))
+        .collect();
+
+    for payment in shared {
+        let month = Month::try_from(payment.timestamp.month() as u8)
+            .map_err(|_| PaymentFetchError::InvalidData)?;
+        let month = MonthDate::new(payment.timestamp.year(), month);
+
+        let user_count = user_counts.get(&payment.id).copied().unwrap_or(0);
+
+        if let Some(month_data) = months.get_mut(&month) {
+            month_data.amount += CalculatedAmount::calculate(payment.amount, false, true, user_count);
+        }
     }
 
     Ok(months.into_values().rev().collect())
 }
 
-pub fn calculate_all_amounts() -> Result<HashMap<Key, CalculatedAmount>, PaymentFetchError> {
+fn parse_month(month: &str) -> Result<MonthDate, PaymentFetchError> {
+    month.parse().map_err(|_| PaymentFetchError::InvalidData)
+}
+
+/// Matches the payments that are shared with at least one user, or with none
+fn with_users(has_users: bool) -> Filter<Payment> {
+    let filter = Payment::id().linked::<PaymentUserLink, User>();
+
+    if has_users {
+        filter
+    } else {
+        !filter
+    }
+}
+
+/// The amount of the owner of payments summing up to `amount`, without the share of the owner.
+///
+/// The owner gets the amount back from the users, unless the payments have none.
+fn owned_amount(amount: i64, has_users: bool) -> CalculatedAmount {
+    // Only whether a payment has users changes the amount of an owner, not how many
+    CalculatedAmount::calculate(amount, true, false, usize::from(has_users))
+}
+
+/// Searches the names of payments, their imported Tink payments and their categories.
+///
+/// The matches are returned newest first, as the ranks of different full-text tables can not be compared.
+pub fn search_payments(
+    user: Key<User>,
+    query: String,
+    limit: usize,
+) -> Result<Vec<ResponsePayment>, PaymentFetchError> {
+    let db = get_db();
+
+    let mut matches = SearchQuery::<Payment>::new(&query)
+        .filter(user_filter(&user))
+        .get_all::<(Key<Payment>, f64)>(&db)?
+        .into_iter()
+        .map(|(payment, _)| payment)
+        .collect::<HashSet<_>>();
+
+    // Imported payments have the same key as their payment
+    let tink_payments = SearchQuery::<TinkPayment>::new(&query)
+        .filter(TinkPayment::id().in_filter(user_filter(&user)))
+        .get_all::<(Key<Payment>, f64)>(&db)?;
+
+    matches.extend(tink_payments.into_iter().map(|(payment, _)| payment));
+
+    let categories = SearchQuery::<Category>::new(&query)
+        .get_all::<(Key<Category>, f64)>(&db)?
+        .into_iter()
+        .map(|(category, _)| category)
+        .collect::<Vec<_>>();
+
+    if !categories.is_empty() {
+        let links = AggregateQuery::<PaymentCategoryLink>::new()
+            .column(PaymentCategoryLink::payment())
+            .filter(
+                PaymentCategoryLink::category()
+                    .in_values(categories)
+                    .and(PaymentCategoryLink::payment().in_filter(user_filter(&user))),
+            )
+            .get_all::<(Key<Payment>,)>(&db)?;
+
+        matches.extend(links.into_iter().map(|(payment,)| payment));
+    }
+
+    let payments = SelectQuery::new()
+        .filter(Payment::id().in_values(matches))
+        .order_by(Payment::timestamp(), Ordering::Descending)
+        .limit(limit)
+        .get_all::<Payment>(&db)?;
+
+    to_response_payments(payments, &db)
+}
+
+pub fn calculate_all_amounts() -> Result<HashMap<Key<User>, CalculatedAmount>, PaymentFetchError> {
     let db = get_db();
 
     let users = SelectQuery::<User>::new()
-        .get_all::<Key>(&db)
-        .ok_or(PaymentFetchError)?;
+        .get_all::<Key<User>>(&db)?;
 
-    let mut users: HashMap<Key, CalculatedAmount> = HashMap::from_iter(
+    let mut users: HashMap<Key<User>, CalculatedAmount> = HashMap::from_iter(
         users
             .into_iter()
             .map(|user| (user, CalculatedAmount::default())),
     );
 
-    let payments = SelectQuery::<Payment>::new()
-        .get_all::<Payment>(&db)
-        .ok_or(PaymentFetchError)?;
-
-    for payment in payments {
-        let payment_users = SelectQuery::<User>::link::<Payment, PaymentUserLink>(payment.id)
-            .get_all::<Key>(&db)
-            .ok_or(PaymentFetchError)?;
-        let user_count = payment_users.len();
-
-        let mut owner_added = false;
-
-        for user in payment_users {
-            let is_owner = user == payment.owner;
-            owner_added = owner_added || is_owner;
-
-            users.entry(user).and_modify(|amount| {
-                *amount += CalculatedAmount::calculate(
-                    payment.amount,
-                    is_owner,
-                    true,
-                    user_count,
-                );
+    for has_users in [true, false] {
+        let owned_amounts = AggregateQuery::<Payment>::new()
+            .column(Payment::owner())
+            .sum(Payment::amount())
+            .filter(with_users(has_users))
+            .group_by(Payment::owner())
+            .get_all::<(Key<User>, Option<i64>)>(&db)?;
+
+        for (owner, amount) in owned_amounts {
+            users.entry(owner).and_modify(|total| {
+                *total += owned_amount(amount.unwrap_or(0), has_users);
             });
         }
+    }
 
-        if !owner_added {
-            users.entry(payment.owner).and_modify(|amount| {
-                *amount += CalculatedAmount::calculate(payment.amount, true, false, user_count);
-            });
-        }
+    let user_counts = AggregateQuery::<PaymentUserLink>::new()
+        .column(PaymentUserLink::payment())
+        .count()
+        .group_by(PaymentUserLink::payment())
+        .get_all::<(Key<Payment>, i64)>(&db)?;
+    let user_counts: HashMap<Key<Payment>, usize> = user_counts
+        .into_iter()
+        .map(|(payment, count)| (payment, count as usize))
+        .collect();
+
+    let amounts = AggregateQuery::<Payment>::new()
+        .column(Payment::id())
+        .column(Payment::amount())
+        .filter(with_users(true))
+        .get_all::<(Key<Payment>, i64)>(&db)?;
+    let amounts: HashMap<Key<Payment>, i64> = amounts.into_iter().collect();
+
+    let links = AggregateQuery::<PaymentUserLink>::new()
+        .column(PaymentUserLink::payment())
+        .column(PaymentUserLink::user())
+        .get_all::<(Key<Payment>, Key<User>)>(&db)?;
+
+    for (payment, user) in links {
+        let (Some(amount), Some(user_count)) = (amounts.get(&payment), user_counts.get(&payment)) else {
+            continue;
+        };
+
+        users.entry(user).and_modify(|total| {
+            *total += CalculatedAmount::calculate(*amount, false, true, *user_count);
+        });
     }
 
     Ok(users)
 }
 
-pub fn insert_payment(id: Option<Key>, owner: Key, payment: AddPaymentData) -> Option<Key> {
+pub fn insert_payment(
+    id: Option<Key<Payment>>,
+    owner: Key<User>,
+    payment: AddPaymentData,
+) -> Result<Key<Payment>, PaymentUpdateError> {
     if !payment.is_valid() {
-        return None;
+        return Err(PaymentUpdateError::InvalidData);
     }
 
-    let id = id.unwrap_or_else(Key::new);
+    let id = id.unwrap_or_else(Key::generate);
 
     let server_payment = Payment {
         id,
         name: payment.name,
         amount: payment.amount,
-        timestamp: payment.timestamp.to_rfc3339(),
+        timestamp: payment.timestamp,
         owner: owner.clone(),
     };
 
     let db = get_db();
 
-    let payment_id = db.insert(server_payment)?;
-
-    for category in payment.categories {
-        db.insert(PaymentCategoryLink {
-            id: Key::new(),
-            payment: payment_id.clone(),
-            category,
-        });
-    }
+    db.transaction(|db| {
+        let payment_id = db.insert(server_payment)?;
 
-    for user in payment.users {
-        db.insert(PaymentUserLink {
-            id: Key::new(),
-            payment: payment_id.clone(),
-            user,
-        });
-    }
+        // The data comes from the client, which only knows untyped keys
+        for category in payment.categories {
+            db.insert(PaymentCategoryLink {
+                payment: payment_id.clone(),
+                category: category.cast(),
+            })?;
+        }
 
-    drop(db);
+        for user in payment.users {
+            db.insert(PaymentUserLink {
+                payment: payment_id.clone(),
+                user: user.cast(),
+            })?;
+        }
 
-    if let Some(tink_payment) = payment.tink {
-        add_tink_payment(payment_id.clone(), owner, tink_payment);
-    }
+        if let Some(tink_payment) = payment.tink {
+            add_tink_payment(payment_id.clone(), owner, tink_payment, Some(db))?;
+        }
 
-    Some(payment_id)
+        Ok(payment_id)
+    })
 }
 
 
-pub fn payment_update_users(request_user: Key, payment_id: Key, users: Vec<Key>) -> Result<(), PaymentUpdateError> {
+pub fn payment_update_users(
+    request_user: Key<User>,
+    payment_id: Key<Payment>,
+    users: Vec<Key<User>>,
+) -> Result<(), PaymentUpdateError> {
     let db = get_db();
 
-    let payment = db.get::<Payment>(payment_id.clone()).ok_or(PaymentUpdateError)?;
+    let payment = db.get::<Payment>(payment_id.clone())?;
 
     if payment.owner != request_user {
-        return Err(PaymentUpdateError);
+        return Err(PaymentUpdateError::NotAllowed);
     }
 
-    let current_links = SelectQuery::<PaymentUserLink>::new().filter(PaymentUserLink::payment().eq(payment_id.clone())).get_all::<Key>(&db).ok_or(PaymentUpdateError)?;
+    db.transaction(|db| {
+        DeleteQuery::filter(PaymentUserLink::payment().eq(payment_id.clone())).run(db)?;
+
+        for user in users {
+            db.insert(PaymentUserLink {
+                payment: payment_id.clone(),
+                user,
+            })?;
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::fixture::{Fixture, FixturePayment, Seeded};
+    use crate::util::month::{Month, MonthDate};
+
+    use super::*;
+
+    #[test]
+    fn get_payments_returns_shared_payments_of_the_month() {
+        let seeded = Fixture::new()
+            .user("alice")
+            .user("bob")
+            .user("carol")
+            .category("food")
+            .payment(
+                FixturePayment::new("groceries", 4200, "alice")
+                    .users(&["alice", "bob"])
+                    .categories(&["food"]),
+            )
+            .payment(FixturePayment::new("rent", 90000, "alice").timestamp("2023-02-01T12:00:00+01:00"))
+            .payment(FixturePayment::new("cinema", 1500, "carol"))
+            .build();
+
+        let payments = get_payments(&seeded.users["bob"], &MonthDate::new(2023, Month::January)).unwrap();
+
+        assert_eq!(payments.len(), 1);
+        assert_eq!(payments[0].id, seeded.payments["groceries"].clone().erase());
+        assert_eq!(payments[0].amount, 4200);
+        assert_eq!(payments[0].owner, seeded.users["alice"].clone().erase());
+        assert_eq!(payments[0].categories, vec![seeded.categories["food"].clone().erase()]);
+    }
 
-    for key in current_links {
-        let _ = db.delete::<PaymentUserLink>(key);
+    fn amount_fixture() -> Seeded {
+        Fixture::new()
+            .user("alice")
+            .user("bob")
+            .payment(FixturePayment::new("groceries", 4200, "alice").users(&["alice", "bob"]))
+            .payment(
+                FixturePayment::new("rent", 90000, "alice")
+                    .users(&["bob"])
+                    .timestamp("2023-02-01T12:00:00+01:00"),
+            )
+            .payment(FixturePayment::new("pizza", 3000, "bob").users(&["alice", "bob"]))
+            .payment(FixturePayment::new("cinema", 1500, "bob"))
+            .build()
     }
 
-    for user in users {
-        db.insert(PaymentUserLink {
-            id: Key::new(),
-            payment: payment_id.clone(),
-            user,
-        }).ok_or(PaymentUpdateError)?;
+    #[test]
+    fn get_months_sums_the_amounts_of_each_month() {
+        let seeded = amount_fixture();
+
+        let months = get_months(seeded.users["alice"].clone()).unwrap();
+
+        let months = months
+            .iter()
+            .map(|month| {
+                (
+                    month.month.to_string(),
+                    month.payments_count,
+                    month.amount.user_amount,
+                    month.amount.repay_amount,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            months,
+            vec![
+                ("2023-02".to_owned(), 1, 90000, -90000),
+                ("2023-01".to_owned(), 2, 4200, -600),
+            ]
+        );
     }
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn calculate_all_amounts_sums_the_amounts_of_each_user() {
+        let seeded = amount_fixture();
+
+        let amounts = calculate_all_amounts().unwrap();
+
+        let alice = &amounts[&seeded.users["alice"]];
+        assert_eq!((alice.user_amount, alice.repay_amount), (94200, -90600));
+
+        let bob = &amounts[&seeded.users["bob"]];
+        assert_eq!((bob.user_amount, bob.repay_amount), (4500, 90600));
+    }
+
+    #[test]
+    fn search_payments_finds_shared_payments_by_name_and_category_newest_first() {
+        let seeded = Fixture::new()
+            .user("alice")
+            .user("bob")
+            .user("carol")
+            .category("takeaway")
+            .payment(FixturePayment::new("pizza night", 3000, "alice").users(&["alice", "bob"]))
+            .payment(FixturePayment::new("pizza", 1200, "carol"))
+            .payment(
+                FixturePayment::new("dinner", 2500, "alice")
+                    .users(&["alice", "bob"])
+                    .categories(&["takeaway"]),
+            )
+            .payment(FixturePayment::new("late dinner", 1800, "carol").categories(&["takeaway"]))
+            .payment(
+                FixturePayment::new("takeaway lunch", 1500, "bob")
+                    .timestamp("2023-02-01T12:00:00+01:00")
+                    .users(&["bob"]),
+            )
+            .build();
+
+        let names = |query: &str, limit: usize| {
+            search_payments(seeded.users["bob"].clone(), query.to_owned(), limit)
+                .unwrap()
+                .into_iter()
+                .map(|payment| payment.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names("pizza", 10), vec!["pizza night"]);
+        assert_eq!(names("takeaway", 10), vec!["takeaway lunch", "dinner"]);
+        assert_eq!(names("takeaway", 1), vec!["takeaway lunch"]);
+        assert_eq!(names("pizza", 0), Vec::<String>::new());
+    }
+
+    #[test]
+    fn insert_payment_rejects_payments_without_users() {
+        let seeded = Fixture::new().user("alice").build();
+
+        let payment = AddPaymentData {
+            name: "groceries".to_owned(),
+            amount: 4200,
+            timestamp: DateTime::parse_from_rfc3339("2023-01-01T12:00:00+01:00").unwrap(),
+            users: Vec::new(),
+            categories: Vec::new(),
+            tink: None,
+        };
+
+        assert!(matches!(
+            insert_payment(None, seeded.users["alice"].clone(), payment),
+            Err(PaymentUpdateError::InvalidData)
+        ));
+    }
+}
@@ -7,18 +7,30 @@ pub use super::data::*;
 use super::server;
 
 #[derive(Debug)]
-pub struct RuleFetchError;
+pub enum RuleFetchError {
+    NotFound,
+    InvalidData,
+    Database(String),
+}
 impl From<RuleFetchError> for ServerFnError {
-    fn from(_: RuleFetchError) -> Self {
-        ServerFnError::ServerError("could not get rule".to_owned())
+    fn from(value: RuleFetchError) -> Self {
+        match value {
+            RuleFetchError::NotFound => ServerFnError::ServerError("could not get rule: not found".to_owned()),
+            RuleFetchError::InvalidData => ServerFnError::ServerError("could not get rule: invalid data".to_owned()),
+            RuleFetchError::Database(err) => ServerFnError::ServerError(format!("could not get rule: {}", err)),
+        }
     }
 }
 
 #[derive(Debug)]
-pub struct RuleInsertError;
+pub enum RuleInsertError {
+    Database(String),
+}
 impl From<RuleInsertError> for ServerFnError {
-    fn from(_: RuleInsertError) -> Self {
-        ServerFnError::ServerError("could not insert rule".to_owned())
+    fn from(value: RuleInsertError) -> Self {
+        match value {
+            RuleInsertError::Database(err) => ServerFnError::ServerError(format!("could not insert rule: {}", err)),
+        }
     }
 }
 
@@ -29,7 +41,7 @@ pub async fn get_rules() -> Result<Vec<Rule>, ServerFnError> {
 
 #[server]
 pub async fn get_rule(id: Key) -> Result<Rule, ServerFnError> {
-    server::get_rule(id.clone()).map_err(Into::into)
+    server::get_rule(id.cast()).map_err(Into::into)
 }
 
 #[server]
@@ -39,7 +51,11 @@ pub async fn add_rule(
     keywords: Vec<String>,
     categories: Vec<Key>,
 ) -> Result<Key, ServerFnError> {
-    server::insert_rule(None, name, shared, keywords, categories).map_err(Into::into)
+    let categories = categories.into_iter().map(Key::cast).collect();
+
+    server::insert_rule(None, name, shared, keywords, categories)
+        .map(Key::erase)
+        .map_err(Into::into)
 }
 
 #[server]
@@ -50,14 +66,18 @@ pub async fn update_rule(
     keywords: Vec<String>,
     categories: Vec<Key>,
 ) -> Result<Key, ServerFnError> {
-    server::insert_rule(Some(id), name, shared, keywords, categories).map_err(Into::into)
+    let categories = categories.into_iter().map(Key::cast).collect();
+
+    server::insert_rule(Some(id.cast()), name, shared, keywords, categories)
+        .map(Key::erase)
+        .map_err(Into::into)
 }
 
 #[server]
 pub async fn delete_rule(
     id: Key
 ) -> Result<(), ServerFnError> {
-    if server::delete_rule(id) {
+    if server::delete_rule(id.cast()) {
         Ok(())
     } else {
         Err(ServerFnError::ServerError("Could not delete".to_string()))
@@ -41,10 +41,11 @@ impl Rule {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ssr", derive(mensula::AsDataType))]
 pub enum ShareRule {
-    NotShared,
-    Shared,
-    Choose,
+    NotShared = 0,
+    Shared = 1,
+    Choose = 2,
 }
 
 impl Default for ShareRule {
@@ -53,16 +54,6 @@ impl Default for ShareRule {
     }
 }
 
-impl From<ShareRule> for i64 {
-    fn from(value: ShareRule) -> Self {
-        match value {
-            ShareRule::NotShared => 0,
-            ShareRule::Shared => 1,
-            ShareRule::Choose => 2,
-        }
-    }
-}
-
 impl TryFrom<i64> for ShareRule {
     type Error = ();
 
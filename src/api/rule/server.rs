@@ -1,4 +1,6 @@
-use mensula::query::{Ordering, SelectQuery};
+use std::collections::HashMap;
+
+use mensula::query::{DeleteQuery, Ordering, SelectQuery};
 use mensula::{Database, Table};
 use mensula_key::Key;
 
@@ -7,57 +9,84 @@ use crate::db::get_db;
 
 use super::api::{Rule as ResponseRule, RuleFetchError, RuleInsertError, ShareRule};
 
+impl From<mensula::Error> for RuleFetchError {
+    fn from(value: mensula::Error) -> Self {
+        match value {
+            mensula::Error::NotFound => RuleFetchError::NotFound,
+            err => RuleFetchError::Database(err.to_string()),
+        }
+    }
+}
+
+impl From<mensula::Error> for RuleInsertError {
+    fn from(value: mensula::Error) -> Self {
+        RuleInsertError::Database(value.to_string())
+    }
+}
+
 #[derive(Table)]
 pub struct Rule {
     #[primary]
-    id: Key,
+    id: Key<Self>,
     name: String,
-    share_rule: i64,
+    #[check("share_rule IN (0, 1, 2)")]
+    #[default(ShareRule::Choose)]
+    share_rule: ShareRule,
 }
 
 #[derive(Table)]
 pub struct RuleCategoryLink {
     #[primary]
-    id: Key,
     #[foreign_link(Rule)]
-    rule: Key,
+    rule: Key<Rule>,
+    #[primary]
     #[foreign_link(Category)]
-    category: Key,
+    category: Key<Category>,
 }
 
 #[derive(Table)]
 pub struct RuleKeyword {
     #[primary]
-    id: Key,
+    id: Key<Self>,
     #[foreign(Rule)]
-    rule: Key,
+    rule: Key<Rule>,
     keyword: String,
 }
 
-fn to_response_rule(rule: Rule, db: &Database) -> Result<ResponseRule, RuleFetchError> {
-    let categories = SelectQuery::<Category>::link::<Rule, RuleCategoryLink>(rule.id.clone())
+fn to_response_rules(rules: Vec<Rule>, db: &Database) -> Result<Vec<ResponseRule>, RuleFetchError> {
+    let ids: Vec<Key<Rule>> = rules.iter().map(|rule| rule.id.clone()).collect();
+
+    let mut categories = SelectQuery::<Category>::new()
         .order_by(Category::name(), Ordering::Ascending)
-        .get_all(&db)
-        .ok_or(RuleFetchError)?;
+        .get_all_linked::<Rule, RuleCategoryLink, _>(db, &ids)?;
+
+    let mut keywords = HashMap::<Key<Rule>, Vec<String>>::new();
 
-    let keywords = SelectQuery::new()
-        .filter(RuleKeyword::rule().eq(rule.id.clone()))
+    let rule_keywords = SelectQuery::new()
+        .filter(RuleKeyword::rule().in_values(ids))
         .order_by(RuleKeyword::keyword(), Ordering::Ascending)
-        .get_all::<RuleKeyword>(db)
-        .ok_or(RuleFetchError)?
+        .get_all::<RuleKeyword>(db)?;
+
+    for keyword in rule_keywords {
+        keywords.entry(keyword.rule).or_default().push(keyword.keyword);
+    }
+
+    let rules = rules
         .into_iter()
-        .map(|keyword| keyword.keyword)
+        .map(|rule| {
+            let id = rule.id;
+
+            ResponseRule {
+                keywords: keywords.remove(&id).unwrap_or_default(),
+                categories: categories.remove(&id).unwrap_or_default(),
+                id: id.erase(),
+                name: rule.name,
+                share_rule: rule.share_rule,
+            }
+        })
         .collect();
 
-    let share_rule = rule.share_rule.try_into().map_err(|_| RuleFetchError)?;
-
-    Ok(ResponseRule {
-        id: rule.id,
-        name: rule.name,
-        keywords,
-        categories,
-        share_rule,
-    })
+    Ok(rules)
 }
 
 pub fn get_rules() -> Result<Vec<ResponseRule>, RuleFetchError> {
@@ -65,84 +94,69 @@ pub fn get_rules() -> Result<Vec<ResponseRule>, RuleFetchError> {
 
     let rules = SelectQuery::new()
         .order_by(Rule::name(), Ordering::Ascending)
-        .get_all(&db)
-        .ok_or(RuleFetchError)?;
+        .get_all(&db)?;
 
-    let rules = Result::from_iter(rules.into_iter().map(|rule| to_response_rule(rule, &db)))?;
-
-    Ok(rules)
+    to_response_rules(rules, &db)
 }
 
-pub fn get_rule(id: Key) -> Result<ResponseRule, RuleFetchError> {
+pub fn get_rule(id: Key<Rule>) -> Result<ResponseRule, RuleFetchError> {
     let db = get_db();
 
     let rule = SelectQuery::new()
         .filter(Rule::id().eq(id))
-        .get_first::<Rule>(&db)
-        .ok_or(RuleFetchError)?;
+        .get_first::<Rule>(&db)?
+        .ok_or(RuleFetchError::NotFound)?;
 
-    to_response_rule(rule, &db)
+    to_response_rules(vec![rule], &db)?
+        .pop()
+        .ok_or(RuleFetchError::NotFound)
 }
 
 pub fn insert_rule(
-    id: Option<Key>,
+    id: Option<Key<Rule>>,
     name: String,
     shared: ShareRule,
     keywords: Vec<String>,
-    categories: Vec<Key>,
-) -> Result<Key, RuleInsertError> {
+    categories: Vec<Key<Category>>,
+) -> Result<Key<Rule>, RuleInsertError> {
     let db = get_db();
 
-    let rule_id = db
-        .insert(Rule {
-            id: id.unwrap_or_else(Key::new),
-            name,
-            share_rule: shared.into(),
-        })
-        .ok_or(RuleInsertError)?;
+    db.transaction(|db| {
+        let rule_id = db
+            .insert(Rule {
+                id: id.unwrap_or_else(Key::generate),
+                name,
+                share_rule: shared,
+            })?;
+
+        DeleteQuery::filter(RuleKeyword::rule().eq(rule_id.clone())).run(db)?;
 
-    if let Some(keywords) = SelectQuery::new()
-        .filter(RuleKeyword::rule().eq(rule_id.clone()))
-        .get_all::<Key>(&db)
-    {
         for keyword in keywords {
-            let _ = db.delete::<RuleKeyword>(keyword);
+            let keyword = clean_keyword(keyword);
+            db.insert(RuleKeyword {
+                id: Key::generate(),
+                rule: rule_id.clone(),
+                keyword,
+            })?;
         }
-    }
 
-    for keyword in keywords {
-        let keyword = clean_keyword(keyword);
-        db.insert(RuleKeyword {
-            id: Key::new(),
-            rule: rule_id.clone(),
-            keyword,
-        });
-    }
+        DeleteQuery::filter(RuleCategoryLink::rule().eq(rule_id.clone())).run(db)?;
 
-    if let Some(cateories) = SelectQuery::new()
-        .filter(RuleCategoryLink::rule().eq(rule_id.clone()))
-        .get_all::<Key>(&db)
-    {
-        for category in cateories {
-            let _ = db.delete::<RuleCategoryLink>(category);
+        for category in categories {
+            db.insert(RuleCategoryLink {
+                rule: rule_id.clone(),
+                category,
+            })?;
         }
-    }
 
-    for category in categories {
-        db.insert(RuleCategoryLink {
-            id: Key::new(),
-            rule: rule_id.clone(),
-            category,
-        });
-    }
-
-    Ok(rule_id)
+        Ok(rule_id)
+    })
 }
 
 fn clean_keyword(keyword: String) -> String {
     keyword.to_lowercase()
 }
 
-pub fn delete_rule(id: Key) -> bool {
+pub fn delete_rule(id: Key<Rule>) -> bool {
     get_db().delete::<Rule>(id).is_ok()
 }
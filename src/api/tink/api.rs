@@ -35,7 +35,10 @@ pub async fn tink_get_payments(month: MonthDate) -> Result<Vec<TinkPayment>, Ser
 pub async fn tink_get_payment_data(id: Key) -> Result<TinkPaymentData, ServerFnError> {
     // let user = crate::auth::get_user().await?;
 
-    server::get_payment_data(id, None).ok_or_else(||ServerFnError::ServerError("Unkown tink payment id".to_string()))
+    server::get_payment_data(id.cast(), None).map_err(|err| match err {
+        mensula::Error::NotFound => ServerFnError::ServerError("Unkown tink payment id".to_string()),
+        err => ServerFnError::ServerError(err.to_string()),
+    })
 }
 
 #[server]
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use chrono::{DateTime, FixedOffset, Local};
 use mensula::query::SelectQuery;
 use mensula::{Database, Table};
@@ -19,13 +21,15 @@ pub struct TinkPayment {
     #[primary]
     #[foreign(Payment)]
     #[on_delete("cascade")]
-    id: Key,
+    id: Key<Payment>,
+    #[fulltext]
     name: String,
+    #[index]
     amount: i64,
-    timestamp: String,
+    timestamp: DateTime<FixedOffset>,
     #[foreign(User)]
     #[on_delete("cascade")]
-    owner: Key,
+    owner: Key<User>,
 }
 
 #[derive(Table)]
@@ -33,22 +37,22 @@ pub struct TinkToken {
     #[primary]
     #[foreign(User)]
     #[on_delete("cascade")]
-    id: Key,
+    id: Key<User>,
     token: String,
-    expires_timestamp: String,
+    expires_timestamp: DateTime<FixedOffset>,
 }
 
-pub fn create_token(user: Key, auth_code: &str) -> Option<AuthToken> {
+pub fn create_token(user: Key<User>, auth_code: &str) -> Option<AuthToken> {
     let token = get_auth_token(auth_code);
 
     if let Some(token) = token {
         let tink_token = TinkToken {
             id: user,
             token: token.token.clone(),
-            expires_timestamp: token.expires_timestamp.to_rfc3339(),
+            expires_timestamp: token.expires_timestamp,
         };
 
-        get_db().insert(tink_token)?;
+        get_db().insert(tink_token).ok()?;
 
         Some(token)
     } else {
@@ -56,11 +60,11 @@ pub fn create_token(user: Key, auth_code: &str) -> Option<AuthToken> {
     }
 }
 
-pub fn get_token(id: Key) -> Option<AuthToken> {
+pub fn get_token(id: Key<User>) -> Option<AuthToken> {
     let db = get_db();
 
     match db.get::<TinkToken>(id.clone()) {
-        Some(token) => {
+        Ok(token) => {
             if let Some(timestamp) = get_timestamp_if_valid(&token) {
                 Some(AuthToken {
                     token: token.token,
@@ -71,23 +75,21 @@ pub fn get_token(id: Key) -> Option<AuthToken> {
                 None
             }
         }
-        None => None,
+        Err(_) => None,
     }
 }
 
 fn get_timestamp_if_valid(token: &TinkToken) -> Option<DateTime<FixedOffset>> {
     let now = Local::now();
 
-    let timestamp = DateTime::parse_from_rfc3339(&token.expires_timestamp).ok()?;
-
-    if now <= timestamp {
-        Some(timestamp)
+    if now <= token.expires_timestamp {
+        Some(token.expires_timestamp)
     } else {
         None
     }
 }
 
-pub fn get_payments(user: Key, month: MonthDate) -> Option<Vec<ResponseTinkPayment>> {
+pub fn get_payments(user: Key<User>, month: MonthDate) -> Option<Vec<ResponseTinkPayment>> {
     let token = get_token(user.clone())?;
 
     let month = TinkMonth {
@@ -110,7 +112,7 @@ pub fn get_payments(user: Key, month: MonthDate) -> Option<Vec<ResponseTinkPayme
     Some(payments)
 }
 
-fn get_status(transaction: &Transaction, user: &Key, db: &Database) -> TinkPaymentStatus {
+fn get_status(transaction: &Transaction, user: &Key<User>, db: &Database) -> TinkPaymentStatus {
     if transaction.status != TransactionStatus::Booked {
         return TinkPaymentStatus::Pending;
     }
@@ -126,15 +128,27 @@ fn get_status(transaction: &Transaction, user: &Key, db: &Database) -> TinkPayme
         )
         .get_first::<Key>(&db);
 
-    if payment.is_some() {
+    if let Ok(Some(_)) = payment {
         TinkPaymentStatus::AlreadyAdded
     } else {
         TinkPaymentStatus::New
     }
 }
 
-pub fn add_tink_payment(payment_id: Key, owner: Key, payment: TinkPaymentData) -> Option<Key> {
-    let db = get_db();
+pub fn add_tink_payment(
+    payment_id: Key<Payment>,
+    owner: Key<User>,
+    payment: TinkPaymentData,
+    db: Option<&Database>,
+) -> mensula::Result<Key<Payment>> {
+    let mutex;
+    let db = match db {
+        Some(db) => db,
+        None => {
+            mutex = get_db();
+            &mutex
+        }
+    };
 
     let TinkPaymentData {
         name,
@@ -146,12 +160,12 @@ pub fn add_tink_payment(payment_id: Key, owner: Key, payment: TinkPaymentData) -
         id: payment_id,
         name,
         amount,
-        timestamp: timestamp.to_rfc3339(),
+        timestamp,
         owner,
     })
 }
 
-pub fn get_payment_data(id: Key, db: Option<&Database>) -> Option<TinkPaymentData> {
+pub fn get_payment_data(id: Key<Payment>, db: Option<&Database>) -> mensula::Result<TinkPaymentData> {
     let mutex;
     let db = match db {
         Some(db) => db,
@@ -161,15 +175,33 @@ pub fn get_payment_data(id: Key, db: Option<&Database>) -> Option<TinkPaymentDat
         }
     };
 
-    db.get::<TinkPayment>(id).and_then(|payment| {
-        Some(TinkPaymentData {
-            name: payment.name,
-            amount: payment.amount,
-            timestamp: DateTime::parse_from_rfc3339(&payment.timestamp).ok()?,
-        })
+    db.get::<TinkPayment>(id).map(|payment| TinkPaymentData {
+        name: payment.name,
+        amount: payment.amount,
+        timestamp: payment.timestamp,
     })
 }
 
+pub fn get_imported_payments(
+    ids: Vec<Key<Payment>>,
+    db: Option<&Database>,
+) -> mensula::Result<HashSet<Key<Payment>>> {
+    let mutex;
+    let db = match db {
+        Some(db) => db,
+        None => {
+            mutex = get_db();
+            &mutex
+        }
+    };
+
+    let imported = SelectQuery::new()
+        .filter(TinkPayment::id().in_values(ids))
+        .get_all::<Key<Payment>>(db)?;
+
+    Ok(imported.into_iter().collect())
+}
+
 pub fn get_tink_url() -> String {
     tink_banking::get_url()
 }
\ No newline at end of file
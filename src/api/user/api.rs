@@ -6,11 +6,18 @@ use super::server;
 
 pub use super::data::*;
 
-pub struct UserFetchError;
+#[derive(Debug)]
+pub enum UserFetchError {
+    NotFound,
+    Database(String),
+}
 
 impl From<UserFetchError> for ServerFnError {
-    fn from(_: UserFetchError) -> Self {
-        ServerFnError::ServerError("could not get user".into())
+    fn from(value: UserFetchError) -> Self {
+        match value {
+            UserFetchError::NotFound => ServerFnError::ServerError("could not get user: not found".into()),
+            UserFetchError::Database(err) => ServerFnError::ServerError(format!("could not get user: {}", err)),
+        }
     }
 }
 
@@ -28,12 +35,14 @@ pub async fn me() -> Result<User, ServerFnError> {
 
 #[server]
 pub async fn get_user(id: Key) -> Result<User, ServerFnError> {
-    server::get_user(id).map_err(Into::into)
+    server::get_user(id.cast()).map_err(Into::into)
 }
 
 #[server]
 pub async fn add_user(name: String, display_name: String, password: String) -> Result<Key, ServerFnError> {
-    server::add_user(name, display_name, password).map_err(Into::into)
+    server::add_user(name, display_name, password)
+        .map(Key::erase)
+        .map_err(Into::into)
 }
 
 #[cfg(feature = "ssr")]
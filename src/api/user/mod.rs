@@ -1,5 +1,5 @@
 #[cfg(feature = "ssr")]
-pub(super) mod server;
+pub(crate) mod server;
 
 mod data;
 mod api;
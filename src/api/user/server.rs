@@ -7,10 +7,20 @@ use mensula_key::Key;
 
 use super::{data::User as ResponseUser, api::UserFetchError};
 
+impl From<mensula::Error> for UserFetchError {
+    fn from(value: mensula::Error) -> Self {
+        match value {
+            mensula::Error::NotFound => UserFetchError::NotFound,
+            err => UserFetchError::Database(err.to_string()),
+        }
+    }
+}
+
 #[derive(Table)]
+#[soft_delete]
 pub struct User {
     #[primary]
-    pub id: Key,
+    pub id: Key<Self>,
     pub name: String,
     pub display_name: String,
     password_hash: String,
@@ -19,7 +29,7 @@ pub struct User {
 impl From<User> for ResponseUser {
     fn from(value: User) -> Self {
         Self {
-            id: value.id,
+            id: value.id.erase(),
             name: value.name,
             display_name: value.display_name,
         }
@@ -28,15 +38,15 @@ impl From<User> for ResponseUser {
 
 pub fn get_all_users() -> Result<Vec<ResponseUser>, UserFetchError> {
     let db = get_db();
-    let users = SelectQuery::new().order_by(User::display_name(), mensula::query::Ordering::Ascending).get_all::<User>(&db).ok_or(UserFetchError)?;
+    let users = SelectQuery::new().order_by(User::display_name(), mensula::query::Ordering::Ascending).get_all::<User>(&db)?;
 
     let users = users.into_iter().map(Into::into).collect();
 
     Ok(users)
 }
 
-pub fn get_user(id: Key) -> Result<ResponseUser, UserFetchError> {
-    let user = get_db().get::<User>(id).ok_or(UserFetchError)?;
+pub fn get_user(id: Key<User>) -> Result<ResponseUser, UserFetchError> {
+    let user = get_db().get::<User>(id)?;
 
     Ok(user.into())
 }
@@ -47,14 +57,16 @@ pub fn get_user_by_name(name: String) -> Option<User> {
     SelectQuery::new()
         .filter(User::name().eq(name.to_owned()))
         .get_first(&db)
+        .ok()
+        .flatten()
 }
 
-pub fn add_user(name: String, display_name: String, password: String) -> Result<Key, UserCreateError> {
+pub fn add_user(name: String, display_name: String, password: String) -> Result<Key<User>, UserCreateError> {
     let db = get_db();
 
     let user = User::create(name, display_name, password)?;
 
-    db.insert(user).ok_or(UserCreateError::Database)
+    db.insert(user).map_err(|err| UserCreateError::Database(err.to_string()))
 }
 
 #[derive(Debug)]
@@ -62,7 +74,7 @@ pub enum UserCreateError {
     InvalidName,
     InvalidDisplayname,
     InvalidPassword,
-    Database,
+    Database(String),
 }
 
 impl Display for UserCreateError {
@@ -71,7 +83,7 @@ impl Display for UserCreateError {
             UserCreateError::InvalidName => write!(f, "Username needs to have at least 2 characters and only contain lowercase letters, numbers and underscores"),
             UserCreateError::InvalidDisplayname => write!(f, "Display name needs to have at least 2 characters"),
             UserCreateError::InvalidPassword => write!(f, "Password needs to have at least 8 characters"),
-            UserCreateError::Database => write!(f, "Could not insert user into database"),
+            UserCreateError::Database(err) => write!(f, "Could not insert user into database: {}", err),
         }
     }
 }
@@ -92,7 +104,7 @@ impl User {
         Self::check_display_name(&display_name)?;
         Self::check_password(&password)?;
 
-        let id = Key::new();
+        let id = Key::generate();
         let password_hash = Self::hash(id.as_ref(), &name, &password);
 
         Ok(Self {
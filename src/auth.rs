@@ -4,6 +4,7 @@ use actix_web_httpauth::extractors::basic::BasicAuth;
 use actix_web_httpauth::extractors::AuthenticationError;
 
 use crate::api::user::get_user_by_name;
+use crate::api::user::server::User;
 use actix_web_httpauth::headers::www_authenticate::basic::Basic;
 use leptos::ServerFnError;
 use mensula::Key;
@@ -12,7 +13,7 @@ pub async fn authenticate_user(
     req: ServiceRequest,
     credentials: BasicAuth,
 ) -> Result<ServiceRequest, (Error, ServiceRequest)> {
-    fn inner(credentials: &BasicAuth) -> Option<Key> {
+    fn inner(credentials: &BasicAuth) -> Option<Key<User>> {
         let password = credentials.password()?;
         get_authenticated_user(credentials.user_id(), password)
     }
@@ -26,7 +27,7 @@ pub async fn authenticate_user(
     }
 }
 
-fn get_authenticated_user(name: &str, password: &str) -> Option<Key> {
+fn get_authenticated_user(name: &str, password: &str) -> Option<Key<User>> {
     let user = get_user_by_name(name.to_owned())?;
 
     if user.authenticate(password) {
@@ -36,13 +37,13 @@ fn get_authenticated_user(name: &str, password: &str) -> Option<Key> {
     }
 }
 
-pub async fn get_user() -> Result<Key, ServerFnError> {
+pub async fn get_user() -> Result<Key<User>, ServerFnError> {
     use actix_web::web;
     use leptos_actix::extract;
 
-    extract(|user: web::ReqData<Key>| async move { (*user).clone() }).await
+    extract(|user: web::ReqData<Key<User>>| async move { (*user).clone() }).await
 }
 
-pub fn get_actix_user<R: HttpMessage>(req: &R) -> Option<Key> {
+pub fn get_actix_user<R: HttpMessage>(req: &R) -> Option<Key<User>> {
     req.extensions().get().cloned()
 }
\ No newline at end of file
@@ -1,6 +1,9 @@
+use std::io::Error;
+
 use mensula_key::Key;
 
 use crate::api::migrate;
+use crate::db::{self, get_db};
 
 #[derive(Debug, clap::Parser)]
 // #[clap(author, version, about)]
@@ -18,13 +21,17 @@ pub struct CliArgs {
 pub enum CliCommand {
     Create(CreateCommand),
     Migrate(MigrateCommand),
+    Backup(BackupCommand),
+    Restore(RestoreCommand),
 }
 
 impl CliCommand {
-    pub fn run(&self) -> std::io::Result<()> {
+    pub fn run(&self, db_file: &str) -> std::io::Result<()> {
         match self {
             CliCommand::Create(command) => command.run(),
             CliCommand::Migrate(command) => command.run(),
+            CliCommand::Backup(command) => command.run(),
+            CliCommand::Restore(command) => command.run(db_file),
         }
     }
 }
@@ -116,4 +123,38 @@ impl MigrateCommand {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Copies the database to `backup_file`, which is safe while the server is running
+#[derive(Debug, clap::Args)]
+pub struct BackupCommand {
+    backup_file: String,
+}
+
+impl BackupCommand {
+    pub fn run(&self) -> std::io::Result<()> {
+        get_db()
+            .backup_to(&self.backup_file)
+            .map_err(|err| Error::other(err.to_string()))?;
+
+        println!("database backed up to '{}'", self.backup_file);
+
+        Ok(())
+    }
+}
+
+/// Replaces the database with the content of `backup_file`
+#[derive(Debug, clap::Args)]
+pub struct RestoreCommand {
+    backup_file: String,
+}
+
+impl RestoreCommand {
+    pub fn run(&self, db_file: &str) -> std::io::Result<()> {
+        db::restore(db_file, &self.backup_file).map_err(|err| Error::other(err.to_string()))?;
+
+        println!("database restored from '{}'", self.backup_file);
+
+        Ok(())
+    }
+}
@@ -3,26 +3,61 @@ use cfg_if::cfg_if;
 
 cfg_if! {
     if #[cfg(feature="ssr")] {
+        use std::cell::RefCell;
         use std::path::Path;
+        use std::time::Duration;
         use once_cell::sync::OnceCell;
-        use std::sync::Mutex;
-        use std::sync::MutexGuard;
-        use mensula::Database;
+        use mensula::{Database, Pool, PooledDatabase, SlowQueryLogger};
 
         use crate::api;
 
-        static DATABASE: OnceCell<Mutex<Database>> = OnceCell::new();
+        const POOL_SIZE: usize = 8;
+        const SLOW_QUERY_THRESHOLD: Duration = Duration::from_millis(100);
+
+        static DATABASE: OnceCell<Pool> = OnceCell::new();
+
+        thread_local! {
+            static INJECTED_DATABASE: RefCell<Option<Pool>> = const { RefCell::new(None) };
+        }
 
         pub fn init<P: AsRef<Path>>(path: P) {
-            let mut db = Database::open(path).expect("could not open db");
+            let pool = Pool::open(path, POOL_SIZE).expect("could not open db");
+            pool.set_logger(SlowQueryLogger::new(SLOW_QUERY_THRESHOLD));
 
-            api::register_tables(&mut db).unwrap();
+            api::register_tables(&mut pool.get().expect("could not open db")).unwrap();
             
-            DATABASE.set(Mutex::new(db)).expect("db already initialized");
+            DATABASE.set(pool).expect("db already initialized");
+        }
+
+        /// Replaces the database at `path` with the backup at `backup_path`.
+        ///
+        /// Restores into a new connection instead of one of the pool, as the other connections
+        /// of the pool would keep the meta and statements of the replaced database,
+        /// and registers the tables again, as the backup may have been made with an older schema.
+        pub fn restore<P: AsRef<Path>, B: AsRef<Path>>(path: P, backup_path: B) -> mensula::Result<()> {
+            let mut database = Database::open(path)?;
+
+            database.restore_from(backup_path)?;
+
+            api::register_tables(&mut database)
         }
 
-        pub fn get_db() -> MutexGuard<'static, Database> {
-            DATABASE.get().expect("database not initialized yet").lock().unwrap()
+        /// Makes [`get_db`] return connections of `pool` on the current thread instead of the global database.
+        ///
+        /// Used to give every test its own database. The pool is dropped when another one is injected
+        /// or the thread exits.
+        #[cfg(test)]
+        pub fn inject(pool: Pool) {
+            INJECTED_DATABASE.with(|injected| *injected.borrow_mut() = Some(pool));
+        }
+
+        pub fn get_db() -> PooledDatabase {
+            let pool = INJECTED_DATABASE
+                .with(|injected| injected.borrow().clone())
+                .or_else(|| DATABASE.get().cloned())
+                .expect("database not initialized yet");
+
+            pool.get().expect("could not open db connection")
         }
     }
-}
\ No newline at end of file
+}
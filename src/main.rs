@@ -12,12 +12,12 @@ async fn main() -> std::io::Result<()> {
 
     let args = CliArgs::parse();
 
-    db::init(
-        args.db_file
-            .as_ref()
-            .map(String::as_str)
-            .unwrap_or("data.sqlite"),
-    );
+    let db_file = args
+        .db_file
+        .as_deref()
+        .unwrap_or("data.sqlite");
+
+    db::init(db_file);
     tink_banking::load_config_from_file(
         args.tink_file
             .as_ref()
@@ -26,7 +26,7 @@ async fn main() -> std::io::Result<()> {
     );
 
     if let Some(command) = args.command {
-        command.run()
+        command.run(db_file)
     } else {
         start_server().await
     }